@@ -1,15 +1,39 @@
 mod util;
 pub use util::alias::*;
+pub use util::audit::{parse_since, AliasUsage, Audit, ReportFormat};
+pub use util::broken::CommandLookup;
+pub use util::config::*;
+pub use util::env_var::{find_env_variables, parse_env_variables, EnvRules, EnvVariable};
+pub use util::function::{
+    find_function_shortcuts, parse_function_names, parse_functions, Arguments, FunctionShortcut,
+};
+pub use util::history::{default_history_file, History, HistoryEntry, HistoryError, HistoryFormat};
+pub use util::ignore::{expand_home, IgnoreRule, IgnoreRules};
+pub use util::input::{read_aliases, AliasInput, RejectedLine};
+pub use util::lint::{lint_aliases, lint_broken, lint_shadowing, LintIssue, LintRule, Severity};
 pub use util::log;
 pub use util::log::init as init_logger;
+pub use util::mining::{mine_aliases, AliasCandidate};
+pub use util::named_dir::{find_named_directories, parse_named_directories, NamedDirectory};
+pub use util::naming::{typing_cost, NameGenerator};
+pub use util::normalize::PathNormalizer;
+pub use util::pattern::{AliasPattern, CompiledPattern, Placeholder, Resolver, Resolvers};
+pub use util::prune::{find_dead_aliases, removal_patch, DeadAlias};
+pub use util::rcfile::{RcReader, RcScan, SkipReason, SkippedLine};
+pub use util::shell::{HookSources, Shell};
+pub use util::stats::{AliasStats, Event, EventKind, EventStore, WeeklyUsage};
+pub use util::strict::{is_escaped, StrictMode};
+pub use util::suggestion::{Suggestion, SuggestionKind};
+pub use util::throttle;
+pub use util::throttle::{CooldownPolicy, ReminderStore};
 use util::{log::*, macros::*, validation};
 
-fn expand_command(aliases: &Vec<Alias>, command: &Command) -> Result<Command, AliasError> {
+fn expand_command(aliases: &[Alias], command: &Command) -> Result<Command, AliasError> {
     debug_value!(aliases, command);
     let needle = command.get().split_whitespace().collect::<Vec<&str>>();
     debug_value!(needle);
 
-    if needle.len() == 0 {
+    if needle.is_empty() {
         trace!(
             "[{}] command is empty; returning empty string",
             function_name!()
@@ -32,7 +56,7 @@ fn expand_command(aliases: &Vec<Alias>, command: &Command) -> Result<Command, Al
             };
 
             debug_value!(expanded);
-            let output = command.get().replace(needle, &expanded.get());
+            let output = command.get().replace(needle, expanded.get());
             debug!("[{}] returning {:?}", function_name!(), output);
             Command::new(&output)
         }
@@ -44,11 +68,53 @@ fn expand_command(aliases: &Vec<Alias>, command: &Command) -> Result<Command, Al
     }
 }
 
+/// Options that narrow down which aliases [find_alias_with_options] returns.
+#[derive(Debug, Clone, Default)]
+pub struct FindOptions {
+    pub match_strategy: MatchStrategy,
+    /// The minimum number of characters an alias must save over its command.
+    pub min_savings: usize,
+    /// Aliases that are never returned, though they're still used for expansion,
+    /// and commands that are meant to be typed in full.
+    pub ignore: IgnoreRules,
+    /// How the placeholders in alias bodies are evaluated; aliases with unresolved ones only match
+    /// when typed literally.
+    pub resolvers: Resolvers,
+    /// How path arguments are normalized before commands are compared.
+    pub paths: PathNormalizer,
+    /// When set, aliases whose command can't be found are never returned.
+    pub commands: Option<CommandLookup>,
+}
+
+impl TryFrom<&Config> for FindOptions {
+    type Error = ConfigError;
+
+    fn try_from(config: &Config) -> Result<Self, Self::Error> {
+        Ok(FindOptions {
+            match_strategy: config.match_strategy,
+            min_savings: config.min_savings,
+            ignore: IgnoreRules::from_config(config)?,
+            resolvers: Resolvers::from_config(config)?,
+            paths: PathNormalizer::new(),
+            commands: None,
+        })
+    }
+}
+
 /// Takes a list of aliases and returns the most matching one
-pub fn find_alias<'a>(haystack: &'a Vec<Alias>, needle: &str) -> Result<Vec<Alias>, AliasError> {
-    debug_value!(haystack, needle);
+pub fn find_alias(haystack: &[Alias], needle: &str) -> Result<Vec<Alias>, AliasError> {
+    find_alias_with_options(haystack, needle, &FindOptions::default())
+}
 
-    if haystack.len() == 0 {
+/// Like [find_alias], but filters and matches according to the given options.
+pub fn find_alias_with_options(
+    haystack: &[Alias],
+    needle: &str,
+    options: &FindOptions,
+) -> Result<Vec<Alias>, AliasError> {
+    debug_value!(haystack, needle, options);
+
+    if haystack.is_empty() {
         trace!("[{}] haystack is empty, leaving", function_name!());
         return Ok(vec![]);
     }
@@ -57,7 +123,12 @@ pub fn find_alias<'a>(haystack: &'a Vec<Alias>, needle: &str) -> Result<Vec<Alia
     debug!("[{}] split command", function_name!());
     debug_value!(command);
 
-    let haystack = validation::filter_invalid_aliases(&haystack);
+    if options.ignore.ignores_command(needle) {
+        trace!("[{}] command is ignored, leaving", function_name!());
+        return Ok(vec![]);
+    }
+
+    let haystack = validation::filter_invalid_aliases(haystack);
     debug!("[{}] filtered successfully", function_name!());
     debug_value!(haystack);
 
@@ -67,6 +138,7 @@ pub fn find_alias<'a>(haystack: &'a Vec<Alias>, needle: &str) -> Result<Vec<Alia
             Ok(Alias {
                 name: Name::from(alias),
                 command: expand_command(&haystack, &alias.command)?,
+                origin: alias.origin.to_owned(),
             })
         })
         .collect::<Result<Vec<Alias>, _>>()?;
@@ -84,20 +156,57 @@ pub fn find_alias<'a>(haystack: &'a Vec<Alias>, needle: &str) -> Result<Vec<Alia
 
     debug_value!(command);
 
+    let normalized: Vec<String> = aliases
+        .iter()
+        .map(|alias| options.paths.normalize_command(alias.command.get()))
+        .collect();
+
+    // Patterns are compiled once, and placeholders resolved once, for every command tried below.
+    let patterns: Vec<Option<CompiledPattern>> = normalized
+        .iter()
+        .map(|normalized| {
+            Some(AliasPattern::parse(normalized))
+                .filter(AliasPattern::has_placeholders)
+                .and_then(|pattern| pattern.compile(&options.resolvers))
+        })
+        .collect();
+
     loop {
+        let typed = options.paths.normalize_command(command.get());
         let matches: Vec<Alias> = aliases
             .iter()
-            .filter(|candidate| candidate.command == command)
+            .zip(patterns.iter().zip(&normalized))
+            .filter(|(_, (pattern, normalized))| match pattern {
+                Some(pattern) => pattern.matches(&typed),
+                None => **normalized == typed,
+            })
+            .map(|(candidate, _)| candidate)
+            .filter(|candidate| !options.ignore.ignores_alias(candidate))
+            .filter(|candidate| match &options.commands {
+                Some(commands) => commands.missing_command(candidate, &aliases).is_none(),
+                None => true,
+            })
+            .filter(|candidate| {
+                let savings =
+                    (candidate.command.get().len()).saturating_sub(candidate.name.get().len());
+                savings >= options.min_savings
+            })
             .map(|candidate| candidate.to_owned())
             .collect();
 
-        if command.get().len() == 0 {
+        if command.get().is_empty() {
             trace!(
                 "[{}] command is empty, breaking out of loop",
                 function_name!()
             );
             break Ok(vec![]);
-        } else if matches.len() == 0 {
+        } else if matches.is_empty() && options.match_strategy == MatchStrategy::Exact {
+            trace!(
+                "[{}] no exact matches, breaking out of loop",
+                function_name!()
+            );
+            break Ok(vec![]);
+        } else if matches.is_empty() {
             trace!("[{}] no matches, trying substring", function_name!());
             debug_value!(command, matches);
             let mut temp: Vec<&str> = command.get().split_whitespace().collect();
@@ -114,7 +223,11 @@ pub fn find_alias<'a>(haystack: &'a Vec<Alias>, needle: &str) -> Result<Vec<Alia
 
 #[cfg(test)]
 mod tests {
-    use crate::{expand_command, find_alias, Alias, Command, NewType};
+    use crate::{
+        expand_command, find_alias, find_alias_with_options, Alias, Command, CommandLookup,
+        FindOptions, IgnoreRule, IgnoreRules, MatchStrategy, NewType, PathNormalizer, Placeholder,
+        Resolver, Resolvers, Shell,
+    };
 
     #[test]
     fn it_matches_only_the_exact_alias() {
@@ -318,4 +431,118 @@ mod tests {
             Ok(vec![Alias::from("gba='git branch --all'").unwrap()])
         );
     }
+
+    #[test]
+    fn it_matches_aliases_with_placeholders() {
+        let aliases: Vec<Alias> = vec![
+            Alias::from("gp='git push'").unwrap(),
+            Alias::from("ggpush='git push origin \"$(git_current_branch)\"'").unwrap(),
+        ];
+
+        // Without a resolver, the branch isn't known, so only `gp` covers the command.
+        assert_eq!(
+            find_alias(&aliases, "git push origin feature-x").unwrap(),
+            vec![aliases[0].to_owned()]
+        );
+
+        let any = |placeholder: Placeholder| Resolvers::new(vec![(placeholder, Resolver::Any)]);
+        let options = FindOptions {
+            resolvers: any(Placeholder::Substitution(
+                "$(git_current_branch)".to_string(),
+            )),
+            ..FindOptions::default()
+        };
+        assert_eq!(
+            find_alias_with_options(&aliases, "git push origin feature-x", &options).unwrap(),
+            vec![aliases[1].to_owned()]
+        );
+        assert_eq!(
+            find_alias_with_options(&aliases, "git push upstream feature-x", &options).unwrap(),
+            vec![aliases[0].to_owned()]
+        );
+
+        let logs = Alias::from("logs='tail -f ~/logs/$(service).log'").unwrap();
+        let options = FindOptions {
+            paths: PathNormalizer::default().with_home(std::path::Path::new("/home/me")),
+            resolvers: any(Placeholder::Substitution("$(service)".to_string())),
+            ..FindOptions::default()
+        };
+        assert_eq!(
+            find_alias_with_options(
+                std::slice::from_ref(&logs),
+                "tail -f /home/me/logs/api.log",
+                &options
+            )
+            .unwrap(),
+            vec![logs]
+        );
+    }
+
+    #[test]
+    fn it_applies_find_options() {
+        let aliases: Vec<Alias> = vec![
+            Alias::from("g='git'").unwrap(),
+            Alias::from("gb='git branch'").unwrap(),
+            Alias::from("gp='git push'").unwrap(),
+        ];
+
+        let exact = FindOptions {
+            match_strategy: MatchStrategy::Exact,
+            ..Default::default()
+        };
+        assert_eq!(
+            find_alias_with_options(&aliases, "git branch -M x", &exact),
+            Ok(vec![])
+        );
+        assert_eq!(
+            find_alias_with_options(&aliases, "git branch", &exact),
+            Ok(vec![Alias::from("gb='git branch'").unwrap()])
+        );
+
+        let min_savings = FindOptions {
+            min_savings: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            find_alias_with_options(&aliases, "git", &min_savings),
+            Ok(vec![])
+        );
+
+        let ignored = FindOptions {
+            ignore: IgnoreRules::new(vec![
+                IgnoreRule::alias("gb").unwrap(),
+                IgnoreRule::command_glob("git push --force").unwrap(),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_alias_with_options(&aliases, "git branch", &ignored),
+            Ok(vec![Alias::from("g='git'").unwrap()])
+        );
+        assert_eq!(
+            find_alias_with_options(&aliases, "git push --force origin", &ignored),
+            Ok(vec![])
+        );
+        assert_eq!(
+            find_alias_with_options(&aliases, "git push origin", &ignored),
+            Ok(vec![Alias::from("gp='git push'").unwrap()])
+        );
+
+        let installed = FindOptions {
+            commands: Some(CommandLookup::new(Shell::Bash).with_functions(["git".to_string()])),
+            ..Default::default()
+        };
+        let aliases = vec![
+            Alias::from("gp='git push'").unwrap(),
+            Alias::from("kp='kubectl push'").unwrap(),
+        ];
+        assert_eq!(
+            find_alias_with_options(&aliases, "kubectl push", &installed),
+            Ok(vec![])
+        );
+        assert_eq!(
+            find_alias_with_options(&aliases, "git push", &installed),
+            Ok(vec![aliases[0].to_owned()])
+        );
+    }
 }
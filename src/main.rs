@@ -1,50 +1,1058 @@
-use alias_helper::{self, find_alias, log::*, Alias};
-use log::LevelFilter;
+use alias_helper::{
+    self, default_history_file, expand_home, find_alias_with_options, find_dead_aliases,
+    find_env_variables, find_function_shortcuts, find_named_directories, is_escaped, lint_aliases,
+    lint_broken, lint_shadowing, log::*, mine_aliases, parse_env_variables, parse_function_names,
+    parse_functions, parse_named_directories, parse_since, read_aliases, removal_patch, throttle,
+    Alias, AliasStats, Audit, CommandLookup, Config, CooldownPolicy, EnvRules, EnvVariable, Event,
+    EventKind, EventStore, FindOptions, FunctionShortcut, History, HistoryEntry, HistoryFormat,
+    HookSources, LintIssue, NameGenerator, NamedDirectory, NewType, OutputFormat, RcReader,
+    ReminderStore, ReportFormat, Severity, Shell, SkipReason, StrictMode, Suggestion,
+};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use log::{log_enabled, Level, LevelFilter};
 use std::{
-    env,
-    io::{self, BufRead},
+    env, fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
     process,
 };
 
 extern crate exitcode;
 
+/// Reminds you of the aliases you already have for the commands you type.
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    subcommand: Option<Subcommands>,
+
+    #[command(flatten)]
+    source: AliasSource,
+
+    /// Exit silently when there are no aliases or nothing matches, as the shell hooks do
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Identifies the shell session for reminder cooldowns; defaults to the terminal or parent process
+    #[arg(long)]
+    session: Option<String>,
+
+    /// Exit with a dedicated status when the command has a matching alias, so the strict hooks cancel it
+    #[arg(long)]
+    strict: bool,
+
+    /// Resolve relative paths in the command and the aliases against this directory before comparing them
+    #[arg(long, value_name = "PATH")]
+    cwd: Option<PathBuf>,
+
+    /// The command that was typed
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+/// Where the aliases are read from.
+#[derive(Args)]
+struct AliasSource {
+    /// The shell whose `alias` output is piped into stdin
+    #[arg(long, default_value = "zsh")]
+    shell: Shell,
+
+    /// Read aliases from this rc file instead of stdin; may be repeated
+    #[arg(long = "rc-file", value_name = "PATH")]
+    rc_files: Vec<PathBuf>,
+
+    /// Read aliases from the configured `rc_files` instead of stdin
+    #[arg(long, conflicts_with = "rc_files")]
+    from_rc: bool,
+
+    /// Read shell functions printed by `typeset -f`, `declare -f` or fish's `functions -an` from this file, to suggest them as shortcuts
+    #[arg(long, value_name = "PATH")]
+    functions: Option<PathBuf>,
+
+    /// Read zsh named directories printed by `hash -d` from this file, to suggest `~name` paths
+    #[arg(long, value_name = "PATH")]
+    named_dirs: Option<PathBuf>,
+
+    /// Read exported variables printed by `env` from this file, to suggest `$NAME` for their values
+    #[arg(long, value_name = "PATH")]
+    env: Option<PathBuf>,
+
+    /// List every alias line that could not be parsed, instead of a summary
+    #[arg(long)]
+    explain: bool,
+
+    /// Exit with an error when any alias line cannot be parsed, to check dotfiles in CI
+    #[arg(long)]
+    deny_parse_errors: bool,
+}
+
+#[derive(Subcommand)]
+enum Subcommands {
+    /// Prints the hook that integrates alias-helper with the given shell
+    Init {
+        shell: Shell,
+
+        /// Cancel commands that have a matching alias; prefix a command with a space or `\` to run it anyway
+        #[arg(long)]
+        strict: bool,
+
+        /// Pass the shell's functions on every command, to suggest function shortcuts and skip broken aliases
+        #[arg(long)]
+        functions: bool,
+
+        /// Pass zsh's named directories on every command, to suggest them
+        #[arg(long)]
+        named_dirs: bool,
+
+        /// Pass the environment on every command, to suggest variables (zsh and bash)
+        #[arg(long)]
+        env: bool,
+    },
+    /// Shows where each definition of the given aliases comes from
+    Explain {
+        #[command(flatten)]
+        source: AliasSource,
+
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Proposes aliases for the commands you type most often, read from your shell history
+    Suggest {
+        #[command(flatten)]
+        source: AliasSource,
+
+        /// The history file to read; defaults to `$HISTFILE` or the shell's default history file
+        #[arg(long, value_name = "PATH")]
+        history: Option<PathBuf>,
+
+        /// How the history is stored; guessed from the file when omitted
+        #[arg(long, value_name = "FORMAT")]
+        history_format: Option<HistoryFormat>,
+
+        /// Only propose commands typed at least this many times
+        #[arg(long, default_value_t = 3)]
+        min_count: usize,
+
+        /// How many aliases to propose
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Shows how often each alias is used instead of its command, and which reminders get ignored
+    Stats {
+        /// How many weeks of trends to show
+        #[arg(long, default_value_t = 8)]
+        weeks: u64,
+    },
+    /// Proposes names for an alias of the given command that don't clash with aliases, executables or builtins
+    Names {
+        #[command(flatten)]
+        source: AliasSource,
+
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Counts how often each alias was missed in your shell history, and which aliases are never used
+    AuditHistory {
+        #[command(flatten)]
+        source: AliasSource,
+
+        /// The history file to read; defaults to `$HISTFILE` or the shell's default history file
+        #[arg(long, value_name = "PATH")]
+        history: Option<PathBuf>,
+
+        /// How the history is stored; guessed from the file when omitted
+        #[arg(long, value_name = "FORMAT")]
+        history_format: Option<HistoryFormat>,
+
+        /// Only audit commands run since this date (`2024-05-01`) or age (`7d`); skips commands without a time
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Print a table, JSON or CSV; defaults to a table, or JSON when `output_format` is json
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<ReportFormat>,
+    },
+    /// Lists the aliases not used in your shell history, directly or through another alias, to prune them
+    Prune {
+        #[command(flatten)]
+        source: AliasSource,
+
+        /// The history file to read; defaults to `$HISTFILE` or the shell's default history file
+        #[arg(long, value_name = "PATH")]
+        history: Option<PathBuf>,
+
+        /// How the history is stored; guessed from the file when omitted
+        #[arg(long, value_name = "FORMAT")]
+        history_format: Option<HistoryFormat>,
+
+        /// Only count commands run since this date (`2024-05-01`) or age (`26w`); skips commands without a time
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Print a patch removing the unused definitions from the rc files they were read from
+        #[arg(long)]
+        patch: bool,
+    },
+    /// Checks the alias definitions for cycles, overrides, duplicates and other problems, failing on errors
+    Lint {
+        #[command(flatten)]
+        source: AliasSource,
+
+        /// Print text or JSON; defaults to `output_format`
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
+
+        /// Look for executables that aliases shadow in these directories instead of `$PATH`
+        #[arg(long, value_name = "PATH")]
+        path: Option<String>,
+    },
+    /// Inspects the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Prints the merged configuration and where each value came from
+    Show,
+}
+
 fn main() {
-    alias_helper::init_logger(LevelFilter::Info);
+    let cli = Cli::parse();
+    let config = Config::load();
+
+    alias_helper::init_logger(match &config {
+        Ok(config) => config.log_level,
+        Err(_) => LevelFilter::Info,
+    });
+
+    let config = config.unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+    for warning in config.warnings() {
+        warn!("{}", warning);
+    }
+
+    match cli.subcommand {
+        Some(Subcommands::Init {
+            shell,
+            strict,
+            functions,
+            named_dirs,
+            env,
+        }) => init(
+            shell,
+            strict,
+            HookSources {
+                functions,
+                named_dirs,
+                env,
+            },
+        ),
+        Some(Subcommands::Config {
+            action: ConfigAction::Show,
+        }) => show_config(&config),
+        Some(Subcommands::Explain { source, names }) => explain(&config, &source, &names),
+        Some(Subcommands::Suggest {
+            source,
+            history,
+            history_format,
+            min_count,
+            limit,
+        }) => suggest(&config, &source, history, history_format, min_count, limit),
+        Some(Subcommands::Stats { weeks }) => stats(&config, weeks),
+        Some(Subcommands::Names { source, command }) => names(&config, &source, &command),
+        Some(Subcommands::AuditHistory {
+            source,
+            history,
+            history_format,
+            since,
+            format,
+        }) => audit_history(&config, &source, history, history_format, since, format),
+        Some(Subcommands::Prune {
+            source,
+            history,
+            history_format,
+            since,
+            patch,
+        }) => prune(&config, &source, history, history_format, since, patch),
+        Some(Subcommands::Lint {
+            source,
+            format,
+            path,
+        }) => lint(&config, &source, format, path),
+        None => find(
+            &config,
+            &cli.source,
+            cli.quiet,
+            cli.strict,
+            cli.session,
+            cli.cwd,
+            cli.command,
+        ),
+    }
+}
 
-    let needle: Vec<String> = env::args().skip(1).collect();
+fn init(shell: Shell, strict: bool, sources: HookSources) {
+    let binary = env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "alias-helper".to_string());
 
-    if needle.len() == 0 {
+    if !strict {
+        print!("{}", shell.init_script(&binary, sources));
+        return;
+    }
+
+    match shell.strict_init_script(&binary, ErrorCode::StrictMatch.into(), sources) {
+        Some(script) => print!("{}", script),
+        None => ErrorCode::StrictUnsupported(shell).log_and_panic("main"),
+    }
+}
+
+fn show_config(config: &Config) {
+    for key in Config::KEYS {
+        if let Some(value) = config.value(key) {
+            println!("{} = {} # {}", key, value, config.source(key));
+        }
+    }
+}
+
+fn find(
+    config: &Config,
+    source: &AliasSource,
+    quiet: bool,
+    strict: bool,
+    session: Option<String>,
+    cwd: Option<PathBuf>,
+    needle: Vec<String>,
+) {
+    if needle.is_empty() {
         ErrorCode::NoCommandInput.log_and_panic("main")
     }
 
-    let stdin = io::stdin();
-    let aliases: Vec<Alias> = stdin
-        .lock()
-        .lines()
-        .filter_map(|s| s.ok())
-        .map(|s| Alias::from(&s))
-        .filter_map(|s| s.ok())
-        .collect();
     let needle: String = needle.join(" ");
 
-    if aliases.len() == 0 {
+    if is_escaped(&needle) {
+        debug!("[main] {:?} is escaped, leaving it alone", needle);
+        process::exit(exitcode::OK);
+    }
+
+    let aliases = source.read(config, quiet);
+    let functions = source.read_functions();
+    let named_directories = source.read_named_directories();
+    let variables = source.read_env_variables(config);
+
+    if aliases.is_empty()
+        && functions.is_empty()
+        && named_directories.is_empty()
+        && variables.is_empty()
+    {
+        if quiet {
+            process::exit(ErrorCode::NoAliasesInput.into());
+        }
         ErrorCode::NoAliasesInput.log_and_panic("main");
     }
 
-    let result = find_alias(&aliases, &needle)
+    let mut options = FindOptions::try_from(config)
+        .unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+    // Without the functions, the aliases that call one would all look broken.
+    if config.skip_broken_aliases && source.functions.is_some() {
+        options.commands = Some(source.command_lookup(None));
+    } else if config.skip_broken_aliases {
+        debug!("[main] functions are unknown, not skipping broken aliases");
+    }
+    if let Some(cwd) = &cwd {
+        options.paths = options.paths.with_cwd(cwd);
+        options.resolvers = options.resolvers.with_cwd(cwd);
+    }
+    let mut result = find_alias_with_options(&aliases, &needle, &options)
         .unwrap_or_else(|err| ErrorCode::from(err).log_and_panic("main"));
 
-    if result.len() > 0 {
+    // Typing an alias expands to its own command, which is using the alias rather than missing it.
+    let typed_alias = needle
+        .split_whitespace()
+        .next()
+        .filter(|word| aliases.iter().any(|alias| alias.name.get() == *word));
+    if let Some(name) = typed_alias {
+        record_events(config, EventKind::Used, &[name], &needle);
+        result.retain(|alias| alias.name.get() != name);
+    }
+    let matched: Vec<&str> = result
+        .iter()
+        .map(|alias| alias.name.get().as_str())
+        .collect();
+    let suggestions = if options.ignore.ignores_command(&needle) {
+        vec![]
+    } else {
+        let mut suggestions = find_function_shortcuts(&functions, &needle);
+        suggestions.extend(find_named_directories(
+            &named_directories,
+            &needle,
+            &options.paths,
+        ));
+        suggestions.extend(find_env_variables(&variables, &needle, &options.paths));
+        suggestions
+    };
+
+    let strict_mode = StrictMode::from_config(config)
+        .unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+
+    if strict && strict_mode.blocks(&needle, &result) {
+        record_events(config, reminder_kind(), &matched, &needle);
         info!(
-            "{}",
-            result
-                .iter()
-                .map(|s| s.to_string())
-                .collect::<Vec<String>>()
-                .join(" ")
+            "Blocked by strict mode: {}\nPrefix the command with a space or `\\` to run it anyway.",
+            render_message(config, &needle, &result, &[])
         );
+        process::exit(ErrorCode::StrictMatch.into());
+    }
+
+    if !result.is_empty() || !suggestions.is_empty() {
+        if !cooldown_allows(config.cooldown, session, &needle, &matched) {
+            debug!("[main] reminder for {:?} is cooling down", needle);
+            record_events(config, EventKind::Suppressed, &matched, &needle);
+            process::exit(exitcode::OK);
+        }
+
+        match config.output_format {
+            OutputFormat::Text => {
+                record_events(config, reminder_kind(), &matched, &needle);
+                info!("{}", render_message(config, &needle, &result, &suggestions))
+            }
+            OutputFormat::Json => {
+                record_events(config, EventKind::Shown, &matched, &needle);
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "command": needle,
+                        "aliases": result,
+                        "suggestions": suggestions,
+                    })
+                )
+            }
+        }
         process::exit(exitcode::OK);
+    } else if quiet {
+        process::exit(ErrorCode::NoOutput.into());
     } else {
         ErrorCode::NoOutput.log_and_panic("main");
     }
 }
+
+/// Prints every definition of the given aliases with its origin; the last one is the one the shell uses.
+fn explain(config: &Config, source: &AliasSource, names: &[String]) {
+    let aliases = source.read(config, false);
+    let host = (io::stdout().is_terminal() && env::var("TERM").is_ok_and(|term| term != "dumb"))
+        .then(hostname);
+
+    for name in names {
+        let definitions: Vec<&Alias> = aliases
+            .iter()
+            .filter(|alias| alias.name.get() == name)
+            .collect();
+
+        let Some((active, overridden)) = definitions.split_last() else {
+            ErrorCode::UnknownAlias(name).log_and_panic("main");
+        };
+
+        for (alias, status) in overridden
+            .iter()
+            .map(|alias| (alias, "overridden"))
+            .chain([(active, "active")])
+        {
+            println!("{} ({})", alias, status);
+
+            if let Some(origin) = &alias.origin {
+                let plugin = match &origin.plugin {
+                    Some(plugin) => format!(", {}", plugin),
+                    None => String::new(),
+                };
+                println!(
+                    "  from {} ({}{})",
+                    origin.location(host.as_deref()),
+                    origin.kind,
+                    plugin
+                );
+                println!("  {}", origin.text);
+            }
+        }
+    }
+}
+
+/// The name of this machine for `file://` links, empty when unknown, which means the local host.
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .or_else(|_| env::var("HOSTNAME"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Prints alias definitions for the frequent history prefixes no alias covers yet, best first.
+fn suggest(
+    config: &Config,
+    source: &AliasSource,
+    history: Option<PathBuf>,
+    history_format: Option<HistoryFormat>,
+    min_count: usize,
+    limit: usize,
+) {
+    let commands: Vec<String> = open_history(source, history, history_format)
+        .map(|entry| entry.command)
+        .collect();
+
+    let aliases = source.read(config, false);
+    let options = FindOptions::try_from(config)
+        .unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+    let mut names = NameGenerator::new(source.shell)
+        .with_aliases(&aliases)
+        .with_env_path();
+    let mut candidates = mine_aliases(&commands, &aliases, &options, &mut names, min_count);
+    candidates.truncate(limit);
+
+    match config.output_format {
+        OutputFormat::Text => {
+            for candidate in &candidates {
+                println!(
+                    "{}  # typed {} times, saves {} characters each",
+                    source.shell.define_alias(&candidate.alias),
+                    candidate.count,
+                    candidate.savings
+                );
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::json!(candidates)),
+    }
+}
+
+/// Prints the adoption of every alias, the weekly trend and the most ignored reminders.
+fn stats(config: &Config, weeks: u64) {
+    let store = EventStore::default_path()
+        .map(|path| EventStore::open(&path))
+        .unwrap_or_default();
+    let aliases = store.aliases();
+    let trend = store.weekly(weeks, throttle::now());
+    let mut ignored: Vec<&AliasStats> = aliases.iter().filter(|stats| stats.ignored > 0).collect();
+    ignored.sort_by(|a, b| b.ignored.cmp(&a.ignored).then(a.alias.cmp(&b.alias)));
+
+    if config.output_format == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "aliases": aliases,
+                "weeks": trend,
+                "ignored": ignored,
+            })
+        );
+        return;
+    }
+
+    if aliases.is_empty() {
+        println!("No events recorded yet.");
+        return;
+    }
+
+    let width = aliases
+        .iter()
+        .map(|stats| stats.alias.len())
+        .max()
+        .unwrap_or_default()
+        .max("ALIAS".len());
+    println!(
+        "{:<width$}  {:>6}  {:>6}  {:>8}",
+        "ALIAS", "USED", "MISSED", "ADOPTION"
+    );
+    for stats in &aliases {
+        println!(
+            "{:<width$}  {:>6}  {:>6}  {:>7.0}%",
+            stats.alias,
+            stats.used,
+            stats.missed,
+            stats.adoption() * 100.0
+        );
+    }
+
+    println!("\nWeekly adoption:");
+    for week in &trend {
+        let start = DateTime::<Utc>::from_timestamp(week.week as i64, 0)
+            .map(|start| start.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let bar = "#".repeat((week.adoption() * 20.0).round() as usize);
+        println!(
+            "{}  {:>6} used  {:>6} missed  {:>4.0}%  {}",
+            start,
+            week.used,
+            week.missed,
+            week.adoption() * 100.0,
+            bar
+        );
+    }
+
+    if !ignored.is_empty() {
+        println!("\nMost ignored reminders:");
+        for stats in ignored.iter().take(5) {
+            println!(
+                "{:<width$}  typed in full {} times after a reminder",
+                stats.alias, stats.ignored
+            );
+        }
+    }
+}
+
+/// Prints the free names for an alias of `command`, best first.
+fn names(config: &Config, source: &AliasSource, command: &[String]) {
+    let aliases = source.read(config, false);
+    let names = NameGenerator::new(source.shell)
+        .with_aliases(&aliases)
+        .with_env_path()
+        .suggest(&command.join(" "));
+
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+/// Prints how often each alias was missed over the history, and the aliases that were never used.
+fn audit_history(
+    config: &Config,
+    source: &AliasSource,
+    history: Option<PathBuf>,
+    history_format: Option<HistoryFormat>,
+    since: Option<DateTime<Utc>>,
+    format: Option<ReportFormat>,
+) {
+    let entries: Vec<HistoryEntry> = open_history(source, history, history_format)
+        .filter(|entry| match since {
+            Some(since) => entry.timestamp.is_some_and(|timestamp| timestamp >= since),
+            None => true,
+        })
+        .collect();
+
+    let aliases = source.read(config, false);
+    let options = FindOptions::try_from(config)
+        .unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+    let audit = Audit::run(&entries, &aliases, &options);
+
+    let format = format.unwrap_or(match config.output_format {
+        OutputFormat::Text => ReportFormat::Table,
+        OutputFormat::Json => ReportFormat::Json,
+    });
+    match format {
+        ReportFormat::Table => print!("{}", audit.to_table()),
+        ReportFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "since": since,
+                "commands": audit.commands,
+                "aliases": audit.aliases,
+                "unused": audit.unused(),
+            })
+        ),
+        ReportFormat::Csv => print!("{}", audit.to_csv()),
+    }
+}
+
+/// Prints the aliases no command in the history used, with the aliases that call them, or a patch removing them.
+fn prune(
+    config: &Config,
+    source: &AliasSource,
+    history: Option<PathBuf>,
+    history_format: Option<HistoryFormat>,
+    since: Option<DateTime<Utc>>,
+    patch: bool,
+) {
+    let entries: Vec<HistoryEntry> = open_history(source, history, history_format)
+        .filter(|entry| match since {
+            Some(since) => entry.timestamp.is_some_and(|timestamp| timestamp >= since),
+            None => true,
+        })
+        .collect();
+
+    let aliases = source.read(config, false);
+    let dead = find_dead_aliases(&entries, &aliases);
+
+    if patch {
+        print!("{}", removal_patch(&dead));
+        return;
+    }
+
+    match config.output_format {
+        OutputFormat::Text => {
+            for dead in &dead {
+                let location = dead
+                    .alias
+                    .origin
+                    .as_ref()
+                    .map(|origin| format!("  # {}", origin.location(None)))
+                    .unwrap_or_default();
+                let dependents = if dead.dependents.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (used by {})", dead.dependents.join(", "))
+                };
+                println!("{}{}{}", dead.alias, location, dependents);
+            }
+            println!(
+                "\n{} of {} aliases unused in {} commands.",
+                dead.len(),
+                aliases.len(),
+                entries.len()
+            );
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "since": since,
+                "commands": entries.len(),
+                "unused": dead,
+            })
+        ),
+    }
+}
+
+/// Prints the issues found in the alias definitions, and exits with an error if any of them is one.
+fn lint(config: &Config, source: &AliasSource, format: Option<OutputFormat>, path: Option<String>) {
+    let rc_files = source.rc_files(config);
+    let (aliases, mut issues) = if rc_files.is_empty() {
+        let input = read_aliases(io::stdin().lock(), source.shell);
+        let rejected: Vec<LintIssue> = input
+            .rejected
+            .iter()
+            .map(LintIssue::from_rejected)
+            .collect();
+        (input.aliases, rejected)
+    } else {
+        let scan = RcReader::read_files(&rc_files);
+        let skipped: Vec<LintIssue> = scan
+            .skipped
+            .iter()
+            .filter_map(LintIssue::from_skipped)
+            .collect();
+        (scan.aliases, skipped)
+    };
+
+    let options = FindOptions::try_from(config)
+        .unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+    issues.extend(lint_aliases(&aliases, source.shell, &options));
+
+    let names = NameGenerator::new(source.shell);
+    let names = match &path {
+        Some(path) => names.with_path(path),
+        None => names.with_env_path(),
+    };
+    issues.extend(lint_shadowing(&aliases, &names));
+    issues.extend(lint_broken(
+        &aliases,
+        &source.command_lookup(path.as_deref()),
+    ));
+    issues.sort_by_key(|issue| {
+        issue
+            .origin
+            .as_ref()
+            .map(|origin| (origin.file.to_owned(), origin.line))
+    });
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+
+    match format.unwrap_or(config.output_format) {
+        OutputFormat::Text => {
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            if !issues.is_empty() {
+                println!(
+                    "\n{} issues in {} aliases, {} errors.",
+                    issues.len(),
+                    aliases.len(),
+                    errors
+                );
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "aliases": aliases.len(),
+                "errors": errors,
+                "issues": issues,
+            })
+        ),
+    }
+
+    if errors > 0 {
+        ErrorCode::LintErrors(errors).log_and_panic("main");
+    }
+}
+
+/// Opens `--history`, or the shell's default history file.
+fn open_history(
+    source: &AliasSource,
+    history: Option<PathBuf>,
+    history_format: Option<HistoryFormat>,
+) -> History {
+    let Some(history) = history.or_else(|| default_history_file(source.shell)) else {
+        ErrorCode::NoHistoryInput.log_and_panic("main");
+    };
+
+    History::open(&history, history_format)
+        .unwrap_or_else(|err| ErrorCode::UnreadableHistory(&history, err).log_and_panic("main"))
+}
+
+impl AliasSource {
+    /// Reads the aliases, reporting the lines that were rejected along the way.
+    fn read(&self, config: &Config, quiet: bool) -> Vec<Alias> {
+        let rc_files = self.rc_files(config);
+
+        if rc_files.is_empty() {
+            let input = read_aliases(io::stdin().lock(), self.shell);
+            let rejected: Vec<String> = input
+                .rejected
+                .iter()
+                .map(|rejected| {
+                    format!(
+                        "line {}: {:?} ({})",
+                        rejected.line, rejected.text, rejected.error
+                    )
+                })
+                .collect();
+
+            let summary = format!("{} alias lines could not be parsed", rejected.len());
+            self.report(&rejected, rejected.len(), &summary, quiet);
+            input.aliases
+        } else {
+            let scan = RcReader::read_files(&rc_files);
+            let rejected: Vec<String> = scan
+                .skipped
+                .iter()
+                .map(|skipped| {
+                    format!(
+                        "{}:{}: {:?} ({})",
+                        skipped.file.display(),
+                        skipped.line,
+                        skipped.text,
+                        skipped.reason
+                    )
+                })
+                .collect();
+            let errors = scan
+                .skipped
+                .iter()
+                .filter(|skipped| {
+                    matches!(
+                        skipped.reason,
+                        SkipReason::InvalidAlias(_) | SkipReason::UnterminatedQuote
+                    )
+                })
+                .count();
+
+            let summary = format!(
+                "{} rc file lines could not be evaluated statically",
+                rejected.len()
+            );
+            self.report(&rejected, errors, &summary, quiet);
+            scan.aliases
+        }
+    }
+
+    /// The rc files to read aliases from; none means reading `alias` output from stdin.
+    fn rc_files(&self, config: &Config) -> Vec<PathBuf> {
+        if self.from_rc {
+            config
+                .rc_files
+                .iter()
+                .map(|path| expand_home(path))
+                .collect()
+        } else {
+            self.rc_files.to_owned()
+        }
+    }
+
+    /// Reads the function shortcuts from `--functions`; a missing file means there are none.
+    fn read_functions(&self) -> Vec<FunctionShortcut> {
+        read_optional(&self.functions)
+            .map(|content| parse_functions(&content))
+            .unwrap_or_default()
+    }
+
+    /// Finds commands in `path`, or `$PATH`, among the builtins and among the functions from `--functions`.
+    fn command_lookup(&self, path: Option<&str>) -> CommandLookup {
+        // fish lists function names alone, one per line or separated by commas.
+        let functions = read_optional(&self.functions)
+            .map(|content| match self.shell {
+                Shell::Fish => content
+                    .split([',', '\n'])
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                _ => parse_function_names(&content),
+            })
+            .unwrap_or_default();
+        let lookup = CommandLookup::new(self.shell).with_functions(functions);
+
+        match path {
+            Some(path) => lookup.with_path(path),
+            None => lookup.with_env_path(),
+        }
+    }
+
+    /// Reads the named directories from `--named-dirs`; a missing file means there are none.
+    fn read_named_directories(&self) -> Vec<NamedDirectory> {
+        read_optional(&self.named_dirs)
+            .map(|content| parse_named_directories(&content))
+            .unwrap_or_default()
+    }
+
+    /// Reads the exported variables from `--env`, skipping the ones the config rules out.
+    fn read_env_variables(&self, config: &Config) -> Vec<EnvVariable> {
+        let Some(content) = read_optional(&self.env) else {
+            return vec![];
+        };
+        let rules = EnvRules::from_config(config)
+            .unwrap_or_else(|err| ErrorCode::InvalidConfig(&err).log_and_panic("main"));
+
+        parse_env_variables(&content, &rules)
+    }
+
+    /// Lists the rejected lines with `--explain`, or summarizes them, and exits if parse errors are fatal.
+    fn report(&self, rejected: &[String], errors: usize, summary: &str, quiet: bool) {
+        if rejected.is_empty() {
+            return;
+        }
+
+        if self.explain {
+            for line in rejected {
+                info!("Rejected {}", line);
+            }
+        } else if quiet {
+            debug!("[main] {}", summary);
+        } else {
+            warn!("{}, run with --explain to list them.", summary);
+        }
+
+        if self.deny_parse_errors && errors > 0 {
+            ErrorCode::ParseErrors(errors).log_and_panic("main");
+        }
+    }
+}
+
+/// Reads the file passed to an optional flag, treating an unreadable file like a missing flag.
+fn read_optional(path: &Option<PathBuf>) -> Option<String> {
+    let path = path.as_ref()?;
+
+    match fs::read(path) {
+        Ok(content) => Some(String::from_utf8_lossy(&content).into_owned()),
+        Err(err) => {
+            debug!("[main] cannot read {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Checks the reminder store against the cooldown policy, and records the reminder when it's allowed.
+/// The adaptive policy instead holds the reminder back once every matched alias has been adopted.
+fn cooldown_allows(
+    policy: CooldownPolicy,
+    session: Option<String>,
+    needle: &str,
+    matched: &[&str],
+) -> bool {
+    match policy {
+        CooldownPolicy::Always => return true,
+        CooldownPolicy::Adaptive(percent) => {
+            let Some(path) = EventStore::default_path() else {
+                return true;
+            };
+            let store = EventStore::open(&path);
+            let now = throttle::now();
+            return matched.is_empty()
+                || matched
+                    .iter()
+                    .any(|alias| !store.is_adopted(alias, percent, now));
+        }
+        _ => {}
+    }
+
+    let Some(path) = ReminderStore::default_path() else {
+        return true;
+    };
+
+    let session = session.unwrap_or_else(throttle::current_session);
+    let now = throttle::now();
+    let mut store = ReminderStore::open(&path);
+
+    if !store.should_remind(policy, &session, needle, now) {
+        return false;
+    }
+
+    store.record(&session, needle, now);
+    if let Err(err) = store.save(&path, now) {
+        debug!("[main] could not save reminders to {:?}: {}", path, err);
+    }
+
+    true
+}
+
+/// Appends an event for each alias to the stats store, keeping the command only when
+/// `record_commands` is on.
+fn record_events(config: &Config, kind: EventKind, aliases: &[&str], needle: &str) {
+    if !config.record_stats || aliases.is_empty() {
+        return;
+    }
+
+    let Some(path) = EventStore::default_path() else {
+        return;
+    };
+
+    let time = throttle::now();
+    let events: Vec<Event> = aliases
+        .iter()
+        .map(|alias| Event {
+            time,
+            kind,
+            alias: alias.to_string(),
+            command: config.record_commands.then(|| needle.to_string()),
+        })
+        .collect();
+
+    if let Err(err) = EventStore::append(&path, &events) {
+        debug!("[main] could not record events to {:?}: {}", path, err);
+    }
+}
+
+/// Whether a reminder logged at the info level reaches the terminal or is filtered out.
+fn reminder_kind() -> EventKind {
+    if log_enabled!(Level::Info) {
+        EventKind::Shown
+    } else {
+        EventKind::Suppressed
+    }
+}
+
+/// Fills the configured message template with the typed command and its matches.
+fn render_message(
+    config: &Config,
+    typed: &str,
+    matches: &[Alias],
+    suggestions: &[Suggestion],
+) -> String {
+    let aliases = matches
+        .iter()
+        .map(|s| s.to_string())
+        .chain(suggestions.iter().map(|s| s.to_string()))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let names = matches
+        .iter()
+        .map(|s| s.name.to_string())
+        .chain(suggestions.iter().map(|s| s.shortcut.to_owned()))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    config
+        .message_template
+        .replace("{aliases}", &aliases)
+        .replace("{names}", &names)
+        .replace("{typed}", typed)
+}
@@ -1,16 +1,20 @@
 use super::log::*;
 use super::macros::*;
-use fancy_regex::Regex;
-use std::fmt;
-
-#[derive(Debug, Clone)]
+use super::shell::Shell;
+use serde::Serialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Name(String);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Command(String);
 
 pub trait NewType<T, U>: Sized {
-    fn new<'a>(value: U) -> Result<Self, AliasError>;
+    fn new(value: U) -> Result<Self, AliasError>;
     fn get(&self) -> &T;
 }
 
@@ -19,27 +23,23 @@ impl NewType<String, &str> for Name {
         &self.0
     }
 
-    fn new<'a>(name: &str) -> Result<Self, AliasError> {
+    /// Validates the name with the rules of `bash`, the strictest of the supported shells.
+    fn new(name: &str) -> Result<Self, AliasError> {
+        Name::for_shell(name, Shell::Bash)
+    }
+}
+
+impl Name {
+    /// Validates the name with the rules of the shell that defined it.
+    pub fn for_shell(name: &str, shell: Shell) -> Result<Name, AliasError> {
         if name.is_empty() {
             ErrorCode::InvalidName(name.to_string()).log_debug(function_name!());
             return Err(AliasError::InvalidName(name.to_string()));
         }
 
-        let regex_pattern = "^[\\w\\d]+$";
-        let regex = unwrap_or_panic_err!(
-            Regex::new(&regex_pattern),
-            ErrorCode::RegexParse,
-            &regex_pattern
-        );
-
-        let regex_passes = regex.is_match(name).unwrap_or_else(|err| {
-            ErrorCode::RegexValidationMatch(&regex, &name, err).log_debug(function_name!());
-            false
-        });
-
-        if !regex_passes {
+        if let Some(character) = shell.illegal_name_char(name) {
             ErrorCode::InvalidName(name.to_string()).log_debug(function_name!());
-            return Err(AliasError::InvalidName(name.to_string()));
+            return Err(AliasError::IllegalNameChar(name.to_string(), character));
         }
 
         Ok(Name(name.to_string()))
@@ -51,7 +51,7 @@ impl NewType<String, &str> for Command {
         &self.0
     }
 
-    fn new<'a>(command: &'a str) -> Result<Self, AliasError> {
+    fn new(command: &str) -> Result<Self, AliasError> {
         Ok(Command(command.to_string()))
     }
 }
@@ -96,45 +96,284 @@ impl PartialEq for Command {
 
 impl Eq for Command {}
 
-#[derive(Clone, Debug, PartialEq)]
+/// How an alias reached alias-helper.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceKind {
+    /// A line of `alias` output piped into stdin.
+    Stdin,
+    /// A statement in an rc file, or in a file it `source`s.
+    RcFile,
+    /// A statement in a file loaded by a plugin manager such as Oh-My-Zsh.
+    Plugin,
+}
+
+/// Where an alias was defined.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Origin {
+    pub kind: SourceKind,
+    /// The file the definition was read from, unless it came from stdin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The line the definition starts at, counting from 1.
+    pub line: usize,
+    /// The column of the alias name on that line, counting characters from 1.
+    pub column: usize,
+    /// The plugin or pack that defined the alias, when it came from one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    /// The definition as it was written, before unquoting.
+    pub text: String,
+    /// Whether the definition carries an `alias-helper: ignore` comment.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub marked: bool,
+}
+
+impl Origin {
+    pub fn stdin(line: usize, text: &str) -> Origin {
+        Origin {
+            kind: SourceKind::Stdin,
+            file: None,
+            line,
+            column: 1,
+            plugin: None,
+            text: text.to_string(),
+            marked: false,
+        }
+    }
+
+    /// The location as `file:line:column`, wrapped in an OSC 8 hyperlink to the file on the host
+    /// named by `hyperlink` when it's set.
+    pub fn location(&self, hyperlink: Option<&str>) -> String {
+        let Some(file) = &self.file else {
+            return format!("stdin:{}:{}", self.line, self.column);
+        };
+
+        let location = format!("{}:{}:{}", file.display(), self.line, self.column);
+        let Some(host) = hyperlink.filter(|_| file.is_absolute()) else {
+            return location;
+        };
+
+        format!(
+            "\x1b]8;;file://{}{}\x1b\\{}\x1b]8;;\x1b\\",
+            host,
+            percent_encode_path(file),
+            location
+        )
+    }
+}
+
+/// Escapes every byte of `path` that can't appear as is in the path of a URL.
+fn percent_encode_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Alias {
     pub name: Name,
     pub command: Command,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+}
+
+/// Aliases are compared by name and command only, wherever they were defined.
+impl PartialEq for Alias {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.command == other.command
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AliasError {
     ParseError(String),
     InvalidName(String),
+    /// The name contains a character its shell does not accept.
+    IllegalNameChar(String, char),
     InvalidCommand(String),
 }
 
 impl Alias {
-    fn is_valid(maybe_alias: &str) -> bool {
-        let regex_pattern = "^\\w+=('|\").*\\1$";
-        let regex = unwrap_or_panic_err!(
-            Regex::new(&regex_pattern),
-            ErrorCode::RegexParse,
-            &regex_pattern
-        );
+    pub fn new(name: Name, command: Command) -> Alias {
+        Alias {
+            name,
+            command,
+            origin: None,
+        }
+    }
 
-        regex.is_match(maybe_alias).unwrap_or_else(|err| {
-            ErrorCode::RegexValidationMatch(&regex, maybe_alias, err).log_debug(function_name!());
-            false
-        })
+    pub fn with_origin(self, origin: Origin) -> Alias {
+        Alias {
+            origin: Some(origin),
+            ..self
+        }
     }
 
     pub fn from(maybe_alias: &str) -> Result<Alias, AliasError> {
+        Alias::from_shell(maybe_alias, Shell::Bash)
+    }
+
+    /// Parses `name='value'`, optionally prefixed with `alias `, checking the name with `shell`'s rules.
+    pub fn from_shell(maybe_alias: &str, shell: Shell) -> Result<Alias, AliasError> {
         debug_value!(maybe_alias);
-        if Alias::is_valid(maybe_alias) {
-            let split: Vec<&str> = maybe_alias.split("=").collect();
-            let name = Name::new(split[0])?;
-            let command = Command::new(&split[1][1..split[1].len() - 1])?;
+        let definition = maybe_alias.strip_prefix("alias ").unwrap_or(maybe_alias);
+
+        let Some((name, value)) = definition.split_once('=') else {
+            ErrorCode::InvalidAlias(maybe_alias.to_string()).log_debug(function_name!());
+            return Err(AliasError::ParseError(maybe_alias.to_string()));
+        };
+
+        if value.is_empty() {
+            ErrorCode::InvalidAlias(maybe_alias.to_string()).log_debug(function_name!());
+            return Err(AliasError::ParseError(maybe_alias.to_string()));
+        }
+
+        // zsh quotes names that contain special characters, such as `'+x'=...`.
+        let name = if name.starts_with(['\'', '"']) {
+            unquote(name)?
+        } else {
+            name.to_string()
+        };
+        let name = Name::for_shell(&name, shell)?;
+        let command = Command::new(&unquote(value)?)?;
+
+        Ok(Alias::new(name, command))
+    }
+}
+
+/// Removes shell quoting from a single word, the way `zsh` and `bash` print alias values.
+///
+/// Supports single quotes, double quotes, `$'...'` strings and backslash escapes,
+/// including concatenations such as `'it'\''s'`. Unquoted whitespace is rejected,
+/// since a value printed by the shell is always a single word.
+pub fn unquote(word: &str) -> Result<String, AliasError> {
+    let parse_error = || AliasError::ParseError(word.to_string());
+    let mut output = String::new();
+    let mut chars = word.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => output.push(c),
+                    None => return Err(parse_error()),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('"' | '\\' | '$' | '`')) => output.push(c),
+                        Some('\n') => {}
+                        Some(c) => {
+                            output.push('\\');
+                            output.push(c);
+                        }
+                        None => return Err(parse_error()),
+                    },
+                    Some(c) => output.push(c),
+                    None => return Err(parse_error()),
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => output.push('\n'),
+                            Some('t') => output.push('\t'),
+                            Some('e') => output.push('\x1b'),
+                            Some(c @ ('\'' | '"' | '\\')) => output.push(c),
+                            Some(c) => {
+                                output.push('\\');
+                                output.push(c);
+                            }
+                            None => return Err(parse_error()),
+                        },
+                        Some(c) => output.push(c),
+                        None => return Err(parse_error()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => output.push(c),
+                None => return Err(parse_error()),
+            },
+            c if c.is_whitespace() => return Err(parse_error()),
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
 
-            return Ok(Alias { name, command });
+/// Splits a command line into words on unquoted whitespace, keeping each word's quotes.
+pub fn split_words(text: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                word.push(c);
+                match chars.next() {
+                    Some(next) => word.push(next),
+                    None => break,
+                }
+                continue;
+            }
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            (None, _) => {}
         }
+        word.push(c);
+    }
 
-        return Err(AliasError::ParseError(maybe_alias.to_string()));
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasError::ParseError(alias) => write!(f, "cannot parse {:?}", alias),
+            AliasError::InvalidName(name) => write!(f, "invalid name {:?}", name),
+            AliasError::IllegalNameChar(name, character) => {
+                write!(f, "name {:?} cannot contain {:?}", name, character)
+            }
+            AliasError::InvalidCommand(command) => write!(f, "invalid command {:?}", command),
+        }
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceKind::Stdin => write!(f, "stdin"),
+            SourceKind::RcFile => write!(f, "rc file"),
+            SourceKind::Plugin => write!(f, "plugin"),
+        }
     }
 }
 
@@ -146,9 +385,9 @@ impl fmt::Display for Alias {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Alias, Command, Name};
+    use crate::{Alias, AliasError, Command, Name, Origin, Shell, SourceKind};
 
-    use super::NewType;
+    use super::{split_words, NewType};
 
     #[test]
     fn it_parses_valid_names() {
@@ -173,6 +412,48 @@ mod tests {
         Name::new("_Abc\\123").unwrap_err();
     }
 
+    #[test]
+    fn it_applies_each_shells_name_rules() {
+        for name in ["..", "...", "g-co", "k8s:ctx", "ll.", "+x", "@", "%"] {
+            for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Nu] {
+                Name::for_shell(name, shell).unwrap();
+            }
+        }
+
+        assert_eq!(
+            Name::for_shell("bin/ls", Shell::Bash),
+            Err(AliasError::IllegalNameChar("bin/ls".to_string(), '/'))
+        );
+        Name::for_shell("bin/ls", Shell::Zsh).unwrap();
+        Name::for_shell("bin/ls", Shell::Fish).unwrap_err();
+        Name::for_shell("$x", Shell::Zsh).unwrap();
+        Name::for_shell("a|b", Shell::Zsh).unwrap_err();
+        Name::for_shell("a$b", Shell::Fish).unwrap();
+        assert_eq!(
+            Name::for_shell("-x", Shell::Fish),
+            Err(AliasError::IllegalNameChar("-x".to_string(), '-'))
+        );
+        Name::for_shell("-x", Shell::Bash).unwrap();
+        Name::for_shell("g=x", Shell::Zsh).unwrap_err();
+
+        assert_eq!(
+            Alias::from_shell("'...'='cd ../..'", Shell::Zsh),
+            Ok(Alias::new(
+                Name::new("...").unwrap(),
+                Command::new("cd ../..").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn it_splits_words_outside_quotes() {
+        assert_eq!(
+            split_words(r#"git commit -m "fix typo"  'a b'\ c"#),
+            vec!["git", "commit", "-m", "\"fix typo\"", r"'a b'\ c"]
+        );
+        assert!(split_words("   ").is_empty());
+    }
+
     #[test]
     fn it_parses_valid_commands() {
         Command::new("").unwrap();
@@ -200,6 +481,58 @@ mod tests {
         Alias::from("bla bla='some blah'").unwrap_err();
     }
 
+    #[test]
+    fn it_formats_origin_locations() {
+        let mut origin = Origin::stdin(3, "g='git'");
+        assert_eq!(origin.location(Some("box")), "stdin:3:1");
+
+        origin.kind = SourceKind::RcFile;
+        origin.file = Some("/home/user/.zshrc".into());
+        origin.column = 7;
+        assert_eq!(origin.location(None), "/home/user/.zshrc:3:7");
+        assert_eq!(
+            origin.location(Some("box")),
+            "\x1b]8;;file://box/home/user/.zshrc\x1b\\/home/user/.zshrc:3:7\x1b]8;;\x1b\\"
+        );
+
+        origin.file = Some("/home/user/my dotfiles/.zshrc".into());
+        assert_eq!(
+            origin.location(Some("box")),
+            "\x1b]8;;file://box/home/user/my%20dotfiles/.zshrc\x1b\\/home/user/my dotfiles/.zshrc:3:7\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn it_parses_shell_quoting() {
+        assert_eq!(
+            Alias::from("a='it'\\''s'").unwrap().command,
+            Command::new("it's").unwrap()
+        );
+        assert_eq!(
+            Alias::from("gb=\"git branch \\\"x\\\"\"").unwrap().command,
+            Command::new("git branch \"x\"").unwrap()
+        );
+        assert_eq!(
+            Alias::from("g=git").unwrap().command,
+            Command::new("git").unwrap()
+        );
+        assert_eq!(
+            Alias::from("alias gst='git status'").unwrap(),
+            Alias::from("gst='git status'").unwrap()
+        );
+        assert_eq!(
+            Alias::from("nl=$'echo a\\nb'").unwrap().command,
+            Command::new("echo a\nb").unwrap()
+        );
+    }
+
+    #[test]
+    fn it_fails_to_parse_broken_quoting() {
+        Alias::from("a='unterminated").unwrap_err();
+        Alias::from("a=\"unterminated").unwrap_err();
+        Alias::from("a=git branch").unwrap_err();
+    }
+
     #[test]
     fn it_gets_name_from_alias() {
         fn test_get_name_from_alias(name: &str) {
@@ -0,0 +1,357 @@
+use super::alias::*;
+use super::history::HistoryEntry;
+use super::log::*;
+use super::macros::*;
+use super::strict::is_escaped;
+use crate::{find_alias_with_options, FindOptions};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// How often an alias was typed, and how often its command was typed in full instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct AliasUsage {
+    pub alias: Alias,
+    /// Commands that start with the alias name.
+    pub used: usize,
+    /// Commands the matcher would have reminded about this alias.
+    pub missed: usize,
+    /// The characters the alias would have saved over its missed commands.
+    pub keystrokes: usize,
+}
+
+/// The result of running a history through the matcher.
+#[derive(Debug, Clone, Serialize)]
+pub struct Audit {
+    /// How many commands were audited.
+    pub commands: usize,
+    /// Every alias, the most keystrokes missed first.
+    pub aliases: Vec<AliasUsage>,
+}
+
+/// The ways `audit-history` prints its report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl Audit {
+    /// Runs every command in `entries` through the matcher, counting each distinct command once.
+    pub fn run(entries: &[HistoryEntry], aliases: &[Alias], options: &FindOptions) -> Audit {
+        let mut typed: HashMap<&str, usize> = HashMap::new();
+        for entry in entries {
+            let command = entry.command.lines().next().unwrap_or_default();
+            if !is_escaped(command) && !command.trim().is_empty() {
+                *typed.entry(command.trim()).or_default() += 1;
+            }
+        }
+        debug!(
+            "[{}] auditing {} distinct commands",
+            function_name!(),
+            typed.len()
+        );
+
+        let mut usages: Vec<AliasUsage> = aliases
+            .iter()
+            .map(|alias| AliasUsage {
+                alias: alias.to_owned(),
+                used: 0,
+                missed: 0,
+                keystrokes: 0,
+            })
+            .collect();
+
+        for (command, count) in typed {
+            let first_word = command.split_whitespace().next().unwrap_or_default();
+            for usage in usages
+                .iter_mut()
+                .filter(|usage| usage.alias.name.get() == first_word)
+            {
+                usage.used += count;
+            }
+
+            let matches =
+                find_alias_with_options(aliases, command, options).unwrap_or_else(|err| {
+                    debug!("[{}] cannot match {:?}: {}", function_name!(), command, err);
+                    vec![]
+                });
+            // Typing the alias itself expands to its command, which isn't a miss.
+            for matched in matches
+                .into_iter()
+                .filter(|matched| matched.name.get() != first_word)
+            {
+                let savings = matched
+                    .command
+                    .get()
+                    .len()
+                    .saturating_sub(matched.name.get().len());
+                for usage in usages
+                    .iter_mut()
+                    .filter(|usage| usage.alias.name == matched.name)
+                {
+                    usage.missed += count;
+                    usage.keystrokes += count * savings;
+                }
+            }
+        }
+
+        usages.sort_by(|a, b| {
+            b.keystrokes
+                .cmp(&a.keystrokes)
+                .then(b.used.cmp(&a.used))
+                .then(a.alias.name.get().cmp(b.alias.name.get()))
+        });
+
+        Audit {
+            commands: entries.len(),
+            aliases: usages,
+        }
+    }
+
+    /// The aliases that were never typed.
+    pub fn unused(&self) -> Vec<&Alias> {
+        self.aliases
+            .iter()
+            .filter(|usage| usage.used == 0)
+            .map(|usage| &usage.alias)
+            .collect()
+    }
+
+    /// The missed aliases as an aligned table, followed by a summary and the unused aliases.
+    pub fn to_table(&self) -> String {
+        let header = ["ALIAS", "COMMAND", "USED", "MISSED", "KEYSTROKES"].map(str::to_string);
+        let rows: Vec<[String; 5]> = self
+            .aliases
+            .iter()
+            .filter(|usage| usage.missed > 0)
+            .map(|usage| {
+                [
+                    usage.alias.name.to_string(),
+                    truncate(usage.alias.command.get(), 40),
+                    usage.used.to_string(),
+                    usage.missed.to_string(),
+                    usage.keystrokes.to_string(),
+                ]
+            })
+            .collect();
+
+        let mut widths = [0; 5];
+        for row in [&header].into_iter().chain(&rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut table = String::new();
+        for row in [&header].into_iter().chain(&rows) {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .enumerate()
+                .map(|(index, (cell, width))| match index {
+                    0 | 1 => format!("{:<width$}", cell),
+                    _ => format!("{:>width$}", cell),
+                })
+                .collect();
+            table += cells.join("  ").trim_end();
+            table += "\n";
+        }
+
+        let keystrokes: usize = self.aliases.iter().map(|usage| usage.keystrokes).sum();
+        table += &format!(
+            "\n{} commands audited, {} aliases missed, costing {} keystrokes.\n",
+            self.commands,
+            rows.len(),
+            keystrokes
+        );
+
+        let unused: Vec<String> = self
+            .unused()
+            .iter()
+            .map(|alias| alias.name.to_string())
+            .collect();
+        if !unused.is_empty() {
+            table += &format!("Never used: {}\n", unused.join(", "));
+        }
+
+        table
+    }
+
+    /// One line per alias, with a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("alias,command,used,missed,keystrokes\n");
+
+        for usage in &self.aliases {
+            csv += &format!(
+                "{},{},{},{},{}\n",
+                csv_field(usage.alias.name.get()),
+                csv_field(usage.alias.command.get()),
+                usage.used,
+                usage.missed,
+                usage.keystrokes
+            );
+        }
+
+        csv
+    }
+}
+
+/// Parses `--since`: a date such as `2024-05-01`, an RFC 3339 time, or an age such as `7d` or `2w`.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.to_utc());
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(Default::default()).and_utc());
+    }
+
+    let age = value
+        .char_indices()
+        .last()
+        .and_then(|(index, unit)| Some((value[..index].parse::<i64>().ok()?, unit)))
+        .and_then(|(count, unit)| match unit {
+            'h' => Duration::try_hours(count),
+            'd' => Duration::try_days(count),
+            'w' => Duration::try_weeks(count),
+            _ => None,
+        });
+
+    match age {
+        Some(age) => Ok(Utc::now() - age),
+        None => Err(format!(
+            "invalid date {:?}, expected YYYY-MM-DD, an RFC 3339 time, or an age such as 7d",
+            value
+        )),
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    match text.char_indices().nth(width.saturating_sub(1)) {
+        Some((index, _)) if text.chars().count() > width => format!("{}…", &text[..index]),
+        _ => text.to_string(),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!(
+                "unsupported report format {:?}, expected one of: table, json, csv",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportFormat::Table => write!(f, "table"),
+            ReportFormat::Json => write!(f, "json"),
+            ReportFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_since, Audit};
+    use crate::{Alias, FindOptions, HistoryEntry};
+    use chrono::DateTime;
+
+    fn entries(commands: &[&str]) -> Vec<HistoryEntry> {
+        commands
+            .iter()
+            .map(|command| HistoryEntry {
+                timestamp: None,
+                command: command.to_string(),
+                cwd: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_counts_missed_and_unused_aliases() {
+        let aliases = vec![
+            Alias::from("gst='git status'").unwrap(),
+            Alias::from("gcm='git commit -m'").unwrap(),
+            Alias::from("kgp='kubectl get pods'").unwrap(),
+        ];
+        let audit = Audit::run(
+            &entries(&[
+                "git status",
+                "git status",
+                "gst",
+                "git commit -m 'fix, again'",
+                "\\git status",
+                "ls",
+            ]),
+            &aliases,
+            &FindOptions::default(),
+        );
+
+        let summary: Vec<(String, usize, usize, usize)> = audit
+            .aliases
+            .iter()
+            .map(|usage| {
+                (
+                    usage.alias.name.to_string(),
+                    usage.used,
+                    usage.missed,
+                    usage.keystrokes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("gst".to_string(), 1, 2, 14),
+                ("gcm".to_string(), 0, 1, 10),
+                ("kgp".to_string(), 0, 0, 0),
+            ]
+        );
+        assert_eq!(audit.commands, 6);
+
+        let unused: Vec<String> = audit
+            .unused()
+            .iter()
+            .map(|alias| alias.name.to_string())
+            .collect();
+        assert_eq!(unused, vec!["gcm", "kgp"]);
+
+        assert!(audit.to_csv().contains("gcm,git commit -m,0,1,10\n"));
+        let table = audit.to_table();
+        assert!(table.starts_with("ALIAS  COMMAND"));
+        assert!(table.contains("Never used: gcm, kgp"));
+    }
+
+    #[test]
+    fn it_parses_since() {
+        assert_eq!(
+            parse_since("2024-05-01"),
+            Ok(DateTime::from_timestamp(1714521600, 0).unwrap())
+        );
+        assert_eq!(
+            parse_since("2024-05-01T02:00:00+02:00"),
+            Ok(DateTime::from_timestamp(1714521600, 0).unwrap())
+        );
+        assert!(parse_since("7d").is_ok());
+        assert!(parse_since("yesterday").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use super::naming::{find_executable, is_builtin, is_executable};
+use super::shell::Shell;
+use std::{collections::HashSet, env, path::Path, path::PathBuf};
+
+/// Words that run the command after them, along with their own flags.
+const PREFIXES: [&str; 10] = [
+    "builtin",
+    "command",
+    "doas",
+    "env",
+    "exec",
+    "noglob",
+    "nocorrect",
+    "nohup",
+    "sudo",
+    "time",
+];
+
+/// Reserved words, which start a compound command rather than name one.
+const KEYWORDS: [&str; 19] = [
+    "!", "[[", "{", "(", "((", "case", "coproc", "do", "done", "elif", "else", "esac", "fi", "for",
+    "function", "if", "select", "until", "while",
+];
+
+/// Finds out whether the commands that aliases run are installed, as executables on `$PATH`,
+/// builtins of the shell or functions.
+#[derive(Debug, Clone)]
+pub struct CommandLookup {
+    shell: Shell,
+    path: Vec<PathBuf>,
+    functions: HashSet<String>,
+}
+
+impl CommandLookup {
+    /// A lookup that only knows the shell's builtins.
+    pub fn new(shell: Shell) -> CommandLookup {
+        CommandLookup {
+            shell,
+            path: vec![],
+            functions: HashSet::new(),
+        }
+    }
+
+    /// Also finds the executables in `path`, a list of directories like `$PATH`.
+    pub fn with_path(self, path: &str) -> CommandLookup {
+        CommandLookup {
+            path: env::split_paths(path).collect(),
+            ..self
+        }
+    }
+
+    /// Finds the executables on `$PATH`.
+    pub fn with_env_path(self) -> CommandLookup {
+        let path = env::var("PATH").unwrap_or_default();
+        self.with_path(&path)
+    }
+
+    /// Also finds the functions with the given names.
+    pub fn with_functions(mut self, names: impl IntoIterator<Item = String>) -> CommandLookup {
+        self.functions.extend(names);
+        self
+    }
+
+    /// Whether `word`, in command position, names something that can run.
+    pub fn resolves(&self, word: &str) -> bool {
+        if word.contains('/') {
+            return is_executable(&expand_tilde(word));
+        }
+
+        KEYWORDS.contains(&word)
+            || is_builtin(self.shell, word)
+            || self.functions.contains(word)
+            || find_executable(&self.path, word).is_some()
+    }
+
+    /// The command `alias` ends up running once the aliases at the start of its body are
+    /// expanded, if it can't be found. An alias that calls itself, like `ls='ls -G'`, runs the
+    /// command it's named after.
+    pub fn missing_command(&self, alias: &Alias, aliases: &[Alias]) -> Option<String> {
+        let mut seen: HashSet<&str> = HashSet::from([alias.name.get().as_str()]);
+        let mut word = first_command(alias.command.get())?;
+
+        while let Some(next) = aliases
+            .iter()
+            .rev()
+            .find(|alias| *alias.name.get() == word && !seen.contains(word.as_str()))
+        {
+            seen.insert(next.name.get());
+            word = first_command(next.command.get())?;
+        }
+
+        debug_value!(alias, word);
+        Some(word).filter(|word| !self.resolves(word))
+    }
+}
+
+/// The first word of `command` that names a command, skipping variable assignments and words
+/// like `sudo` or `command` that run the rest, or nothing for an empty command.
+fn first_command(command: &str) -> Option<String> {
+    let mut after_prefix = false;
+
+    for word in split_words(command) {
+        let word = unquote(&word).unwrap_or(word);
+
+        if is_assignment(&word) || (after_prefix && word.starts_with('-')) {
+            continue;
+        }
+        if PREFIXES.contains(&word.as_str()) {
+            after_prefix = true;
+            continue;
+        }
+
+        return Some(word.strip_prefix('\\').unwrap_or(&word).to_string());
+    }
+
+    trace!("[{}] no command in {:?}", function_name!(), command);
+    None
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn expand_tilde(word: &str) -> PathBuf {
+    match (word.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(word),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommandLookup;
+    use crate::{Alias, Shell};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_finds_aliases_whose_command_is_missing() {
+        let temp = TempDir::new().unwrap();
+        let bin = temp.path();
+        let git = bin.join("git");
+        fs::write(&git, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&git, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let aliases: Vec<Alias> = [
+            "g=git",
+            "gst='g status'",
+            "k=kubectl",
+            "kgp='k get pods'",
+            "ga='GIT_EDITOR=vim command git add'",
+            "up='sudo -E apt update'",
+            "mk='mkcd build'",
+            "back='cd -'",
+            "x='z'",
+            "z='x'",
+        ]
+        .into_iter()
+        .map(|line| Alias::from(line).unwrap())
+        .collect();
+        let lookup = CommandLookup::new(Shell::Zsh)
+            .with_path(&bin.display().to_string())
+            .with_functions(["mkcd".to_string()]);
+
+        let missing: Vec<(String, String)> = aliases
+            .iter()
+            .filter_map(|alias| {
+                let missing = lookup.missing_command(alias, &aliases)?;
+                Some((alias.name.to_string(), missing))
+            })
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                ("k".to_string(), "kubectl".to_string()),
+                ("kgp".to_string(), "kubectl".to_string()),
+                ("up".to_string(), "apt".to_string()),
+                ("x".to_string(), "x".to_string()),
+                ("z".to_string(), "z".to_string()),
+            ]
+        );
+
+        let bash = CommandLookup::new(Shell::Bash);
+        for line in ["w='wait'", "so='shopt -s'", "m='mapfile -t lines'"] {
+            let alias = Alias::from(line).unwrap();
+            assert_eq!(bash.missing_command(&alias, &[]), None, "{}", line);
+        }
+        let fish = CommandLookup::new(Shell::Fish);
+        let shopt = Alias::from("so='shopt -s'").unwrap();
+        assert_eq!(fish.missing_command(&shopt, &[]), Some("shopt".to_string()));
+    }
+}
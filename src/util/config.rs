@@ -0,0 +1,557 @@
+use super::log::*;
+use super::macros::*;
+use super::throttle::CooldownPolicy;
+use log::LevelFilter;
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use toml::Value;
+
+/// How matches are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How much of the typed command an alias has to cover to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrategy {
+    /// Matches the longest alias covering the beginning of the command.
+    #[default]
+    Prefix,
+    /// Matches only aliases covering the whole command.
+    Exact,
+}
+
+/// Where a configuration value was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    UnknownKey(String, ConfigSource),
+    InvalidValue(String, ConfigSource),
+}
+
+/// The merged configuration, along with the source of every value.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub log_level: LevelFilter,
+    pub output_format: OutputFormat,
+    pub message_template: String,
+    pub ignored_aliases: Vec<String>,
+    pub ignored_commands: Vec<String>,
+    pub ignored_command_patterns: Vec<String>,
+    pub ignored_sources: Vec<String>,
+    pub rc_files: Vec<String>,
+    pub min_savings: usize,
+    pub match_strategy: MatchStrategy,
+    pub cooldown: CooldownPolicy,
+    pub strict_exempt_aliases: Vec<String>,
+    pub strict_exempt_commands: Vec<String>,
+    pub placeholder_resolvers: Vec<String>,
+    pub env_min_length: usize,
+    pub env_ignored_variables: Vec<String>,
+    pub record_stats: bool,
+    pub record_commands: bool,
+    pub skip_broken_aliases: bool,
+    sources: BTreeMap<String, ConfigSource>,
+    warnings: Vec<String>,
+}
+
+const ENV_PREFIX: &str = "ALIAS_HELPER_";
+const LIST_KEYS: [&str; 9] = [
+    "ignored_aliases",
+    "ignored_commands",
+    "ignored_command_patterns",
+    "ignored_sources",
+    "rc_files",
+    "strict_exempt_aliases",
+    "strict_exempt_commands",
+    "placeholder_resolvers",
+    "env_ignored_variables",
+];
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            log_level: LevelFilter::Info,
+            output_format: OutputFormat::Text,
+            message_template: "{aliases}".to_string(),
+            ignored_aliases: vec![],
+            ignored_commands: vec![],
+            ignored_command_patterns: vec![],
+            ignored_sources: vec![],
+            rc_files: [
+                "~/.zshrc",
+                "~/.bashrc",
+                "~/.bash_aliases",
+                "~/.config/fish/config.fish",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            min_savings: 0,
+            match_strategy: MatchStrategy::Prefix,
+            cooldown: CooldownPolicy::Always,
+            strict_exempt_aliases: vec![],
+            strict_exempt_commands: vec![],
+            placeholder_resolvers: vec!["$(git_current_branch)=git-branch".to_string()],
+            env_min_length: 6,
+            env_ignored_variables: [
+                "_",
+                "PWD",
+                "OLDPWD",
+                "HOME",
+                "USER",
+                "LOGNAME",
+                "SHELL",
+                "PATH",
+                "MANPATH",
+                "TERM",
+                "TERM_*",
+                "COLORTERM",
+                "SHLVL",
+                "LANG",
+                "LC_*",
+                "HOSTNAME",
+                "TMPDIR",
+                "XDG_*",
+                "SSH_*",
+                "DISPLAY",
+                "LS_COLORS",
+                "LSCOLORS",
+                "PS1",
+                "PS2",
+                "PS4",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            record_stats: true,
+            record_commands: false,
+            skip_broken_aliases: false,
+            sources: BTreeMap::new(),
+            warnings: vec![],
+        }
+    }
+}
+
+impl Config {
+    /// Every key, in the order `config show` prints them.
+    pub const KEYS: [&'static str; 19] = [
+        "log_level",
+        "output_format",
+        "message_template",
+        "ignored_aliases",
+        "ignored_commands",
+        "ignored_command_patterns",
+        "ignored_sources",
+        "rc_files",
+        "min_savings",
+        "match_strategy",
+        "cooldown",
+        "strict_exempt_aliases",
+        "strict_exempt_commands",
+        "placeholder_resolvers",
+        "env_min_length",
+        "env_ignored_variables",
+        "record_stats",
+        "record_commands",
+        "skip_broken_aliases",
+    ];
+
+    /// Loads the configuration from the system, user and project files, then applies `ALIAS_HELPER_*` overrides.
+    pub fn load() -> Result<Config, ConfigError> {
+        let vars = env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        });
+        Config::load_from(&Config::default_paths(), vars)
+    }
+
+    /// Layers the given files in order, ignoring missing ones, then applies the environment variables.
+    /// Unknown keys are an error in files, while unknown variables are ignored with a warning, kept
+    /// in [Config::warnings] since the logger is set up from the loaded configuration.
+    pub fn load_from(
+        paths: &[PathBuf],
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+
+        for path in paths {
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    trace!("[{}] skipping missing {:?}", function_name!(), path);
+                    continue;
+                }
+                Err(err) => return Err(ConfigError::Read(path.to_owned(), err)),
+            };
+
+            let table: toml::Table = content
+                .parse()
+                .map_err(|err| ConfigError::Parse(path.to_owned(), err))?;
+            debug_value!(path, table);
+
+            for (key, value) in table {
+                config.set(&key, value, ConfigSource::File(path.to_owned()))?;
+            }
+        }
+
+        for (name, raw) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = key.to_lowercase();
+            // Other tools may share the prefix, so unlike a typo in a file this isn't an error.
+            if !Config::KEYS.contains(&key.as_str()) {
+                config.warnings.push(format!(
+                    "Ignoring {}, which is not a configuration key.",
+                    name
+                ));
+                continue;
+            }
+            let value = if LIST_KEYS.contains(&key.as_str()) {
+                Value::Array(
+                    raw.split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(|item| Value::String(item.to_string()))
+                        .collect(),
+                )
+            } else {
+                Value::String(raw)
+            };
+
+            config.set(&key, value, ConfigSource::Env(name.to_owned()))?;
+        }
+
+        debug_value!(config);
+        Ok(config)
+    }
+
+    /// The configuration files, from the lowest to the highest precedence.
+    pub fn default_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from("/etc/alias-helper/config.toml")];
+
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+        if let Some(config_home) = config_home {
+            paths.push(config_home.join("alias-helper").join("config.toml"));
+        }
+
+        if let Ok(cwd) = env::current_dir() {
+            if let Some(project) = cwd
+                .ancestors()
+                .map(|dir| dir.join(".alias-helper.toml"))
+                .find(|path| path.is_file())
+            {
+                paths.push(project);
+            }
+        }
+
+        paths
+    }
+
+    /// The problems found while loading that weren't worth an error.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns where the value of `key` came from.
+    pub fn source(&self, key: &str) -> &ConfigSource {
+        self.sources.get(key).unwrap_or(&ConfigSource::Default)
+    }
+
+    /// Returns the value of `key` formatted as TOML.
+    pub fn value(&self, key: &str) -> Option<Value> {
+        let strings = |list: &Vec<String>| {
+            Value::Array(list.iter().map(|s| Value::String(s.to_owned())).collect())
+        };
+
+        let value = match key {
+            "log_level" => Value::String(self.log_level.to_string().to_lowercase()),
+            "output_format" => Value::String(self.output_format.to_string()),
+            "message_template" => Value::String(self.message_template.to_owned()),
+            "ignored_aliases" => strings(&self.ignored_aliases),
+            "ignored_commands" => strings(&self.ignored_commands),
+            "ignored_command_patterns" => strings(&self.ignored_command_patterns),
+            "ignored_sources" => strings(&self.ignored_sources),
+            "rc_files" => strings(&self.rc_files),
+            "min_savings" => Value::Integer(self.min_savings as i64),
+            "match_strategy" => Value::String(self.match_strategy.to_string()),
+            "cooldown" => Value::String(self.cooldown.to_string()),
+            "strict_exempt_aliases" => strings(&self.strict_exempt_aliases),
+            "strict_exempt_commands" => strings(&self.strict_exempt_commands),
+            "placeholder_resolvers" => strings(&self.placeholder_resolvers),
+            "env_min_length" => Value::Integer(self.env_min_length as i64),
+            "env_ignored_variables" => strings(&self.env_ignored_variables),
+            "record_stats" => Value::Boolean(self.record_stats),
+            "record_commands" => Value::Boolean(self.record_commands),
+            "skip_broken_aliases" => Value::Boolean(self.skip_broken_aliases),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    fn set(&mut self, key: &str, value: Value, source: ConfigSource) -> Result<(), ConfigError> {
+        debug_value!(key, value, source);
+        let invalid = || ConfigError::InvalidValue(key.to_string(), source.to_owned());
+
+        match key {
+            "log_level" => self.log_level = parse(&value).ok_or_else(invalid)?,
+            "output_format" => self.output_format = parse(&value).ok_or_else(invalid)?,
+            "message_template" => {
+                self.message_template = value.as_str().ok_or_else(invalid)?.to_string()
+            }
+            "ignored_aliases" => self.ignored_aliases = strings(&value).ok_or_else(invalid)?,
+            "ignored_commands" => self.ignored_commands = strings(&value).ok_or_else(invalid)?,
+            "ignored_command_patterns" => {
+                self.ignored_command_patterns = strings(&value).ok_or_else(invalid)?
+            }
+            "ignored_sources" => self.ignored_sources = strings(&value).ok_or_else(invalid)?,
+            "rc_files" => self.rc_files = strings(&value).ok_or_else(invalid)?,
+            "min_savings" => self.min_savings = parse(&value).ok_or_else(invalid)?,
+            "match_strategy" => self.match_strategy = parse(&value).ok_or_else(invalid)?,
+            "cooldown" => self.cooldown = parse(&value).ok_or_else(invalid)?,
+            "strict_exempt_aliases" => {
+                self.strict_exempt_aliases = strings(&value).ok_or_else(invalid)?
+            }
+            "strict_exempt_commands" => {
+                self.strict_exempt_commands = strings(&value).ok_or_else(invalid)?
+            }
+            "placeholder_resolvers" => {
+                self.placeholder_resolvers = strings(&value).ok_or_else(invalid)?
+            }
+            "env_min_length" => self.env_min_length = parse(&value).ok_or_else(invalid)?,
+            "env_ignored_variables" => {
+                self.env_ignored_variables = strings(&value).ok_or_else(invalid)?
+            }
+            "record_stats" => self.record_stats = parse(&value).ok_or_else(invalid)?,
+            "record_commands" => self.record_commands = parse(&value).ok_or_else(invalid)?,
+            "skip_broken_aliases" => {
+                self.skip_broken_aliases = parse(&value).ok_or_else(invalid)?
+            }
+            _ => return Err(ConfigError::UnknownKey(key.to_string(), source)),
+        }
+
+        self.sources.insert(key.to_string(), source);
+        Ok(())
+    }
+}
+
+/// Parses a TOML scalar through [FromStr], so that files and environment variables accept the same spelling.
+fn parse<T: FromStr>(value: &Value) -> Option<T> {
+    match value {
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Integer(i) => i.to_string().parse().ok(),
+        Value::Boolean(b) => b.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+fn strings(value: &Value) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string))
+        .collect()
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "unsupported output format {:?}, expected text or json",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for MatchStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "prefix" => Ok(MatchStrategy::Prefix),
+            "exact" => Ok(MatchStrategy::Exact),
+            _ => Err(format!(
+                "unsupported match strategy {:?}, expected prefix or exact",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MatchStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchStrategy::Prefix => write!(f, "prefix"),
+            MatchStrategy::Exact => write!(f, "exact"),
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Env(name) => write!(f, "environment variable {}", name),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => {
+                write!(f, "could not parse {}: {}", path.display(), err)
+            }
+            ConfigError::UnknownKey(key, source) => {
+                write!(f, "unknown configuration key {:?} in {}", key, source)
+            }
+            ConfigError::InvalidValue(key, source) => {
+                write!(f, "invalid value for {:?} in {}", key, source)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigError, ConfigSource, MatchStrategy, OutputFormat};
+    use crate::CooldownPolicy;
+    use log::LevelFilter;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use tempfile::TempDir;
+
+    fn write_config(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn it_uses_defaults_without_sources() {
+        let config =
+            Config::load_from(&[PathBuf::from("/nonexistent/config.toml")], vec![]).unwrap();
+
+        assert_eq!(config.log_level, LevelFilter::Info);
+        assert_eq!(config.output_format, OutputFormat::Text);
+        assert_eq!(config.match_strategy, MatchStrategy::Prefix);
+        assert_eq!(config.source("min_savings"), &ConfigSource::Default);
+    }
+
+    #[test]
+    fn it_layers_files_and_environment() {
+        let dir = TempDir::new().unwrap();
+        let system = write_config(
+            dir.path(),
+            "system.toml",
+            "log_level = \"warn\"\nmin_savings = 2\n",
+        );
+        let user = write_config(
+            dir.path(),
+            "user.toml",
+            "min_savings = 4\nignored_aliases = [\"g\"]\nmatch_strategy = \"exact\"\ncooldown = \"daily:3\"\n",
+        );
+        let vars = vec![
+            ("ALIAS_HELPER_OUTPUT_FORMAT".to_string(), "json".to_string()),
+            (
+                "ALIAS_HELPER_IGNORED_COMMANDS".to_string(),
+                "git push --force, rm".to_string(),
+            ),
+            ("UNRELATED".to_string(), "1".to_string()),
+            ("ALIAS_HELPER_COLOUR".to_string(), "1".to_string()),
+        ];
+
+        let config = Config::load_from(&[system.clone(), user.clone()], vars).unwrap();
+
+        assert_eq!(config.log_level, LevelFilter::Warn);
+        assert_eq!(config.source("log_level"), &ConfigSource::File(system));
+        assert_eq!(config.min_savings, 4);
+        assert_eq!(config.source("min_savings"), &ConfigSource::File(user));
+        assert_eq!(config.ignored_aliases, vec!["g"]);
+        assert_eq!(config.match_strategy, MatchStrategy::Exact);
+        assert_eq!(config.cooldown, CooldownPolicy::Daily(3));
+        assert_eq!(config.output_format, OutputFormat::Json);
+        assert_eq!(
+            config.ignored_commands,
+            vec!["git push --force".to_string(), "rm".to_string()]
+        );
+        assert_eq!(
+            config.source("output_format"),
+            &ConfigSource::Env("ALIAS_HELPER_OUTPUT_FORMAT".to_string())
+        );
+        assert_eq!(
+            config.warnings(),
+            ["Ignoring ALIAS_HELPER_COLOUR, which is not a configuration key."]
+        );
+    }
+
+    #[test]
+    fn it_rejects_unknown_keys_and_invalid_values() {
+        let dir = TempDir::new().unwrap();
+        let unknown = write_config(dir.path(), "unknown.toml", "colour = true\n");
+        let invalid = write_config(dir.path(), "invalid.toml", "min_savings = \"lots\"\n");
+
+        assert!(matches!(
+            Config::load_from(&[unknown], vec![]),
+            Err(ConfigError::UnknownKey(..))
+        ));
+        assert!(matches!(
+            Config::load_from(&[invalid], vec![]),
+            Err(ConfigError::InvalidValue(..))
+        ));
+        assert!(matches!(
+            Config::load_from(
+                &[],
+                vec![(
+                    "ALIAS_HELPER_MATCH_STRATEGY".to_string(),
+                    "fuzzy".to_string()
+                )]
+            ),
+            Err(ConfigError::InvalidValue(..))
+        ));
+    }
+
+    #[test]
+    fn it_formats_every_key() {
+        let config = Config::default();
+
+        for key in Config::KEYS {
+            assert!(config.value(key).is_some(), "{} has no value", key);
+        }
+        assert_eq!(config.value("nope"), None);
+    }
+}
@@ -0,0 +1,282 @@
+use super::alias::*;
+use super::config::*;
+use super::ignore::glob_to_regex;
+use super::log::*;
+use super::macros::*;
+use super::normalize::PathNormalizer;
+use super::suggestion::*;
+use fancy_regex::Regex;
+
+/// An exported variable whose value can be typed as `$NAME`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVariable {
+    pub name: String,
+    pub value: String,
+}
+
+/// Which variables are worth suggesting, configured by `env_min_length` and `env_ignored_variables`.
+#[derive(Debug, Clone, Default)]
+pub struct EnvRules {
+    min_length: usize,
+    ignored: Vec<Regex>,
+}
+
+impl EnvRules {
+    pub fn new(min_length: usize, ignored: Vec<Regex>) -> EnvRules {
+        EnvRules {
+            min_length,
+            ignored,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<EnvRules, ConfigError> {
+        let ignored = config
+            .env_ignored_variables
+            .iter()
+            .map(|glob| Regex::new(&format!("^{}$", glob_to_regex(glob))).map_err(Box::new))
+            .collect::<Result<Vec<Regex>, _>>()
+            .map_err(|_| {
+                ConfigError::InvalidValue(
+                    "env_ignored_variables".to_string(),
+                    config.source("env_ignored_variables").to_owned(),
+                )
+            })?;
+
+        Ok(EnvRules::new(config.env_min_length, ignored))
+    }
+
+    /// Whether the variable is too short or too common to suggest.
+    pub fn skips(&self, variable: &EnvVariable) -> bool {
+        variable.value.chars().count() < self.min_length
+            || self.ignored.iter().any(|regex| {
+                regex.is_match(&variable.name).unwrap_or_else(|err| {
+                    ErrorCode::RegexValidationMatch(regex, &variable.name, err)
+                        .log_debug(function_name!());
+                    false
+                })
+            })
+    }
+}
+
+/// Reads the `NAME=value` lines printed by `env`, keeping the variables `rules` don't skip.
+/// Variables whose value spans several lines are dropped, since they can't be told apart from
+/// the lines that follow them.
+pub fn parse_env_variables(output: &str, rules: &EnvRules) -> Vec<EnvVariable> {
+    let mut variables: Vec<Option<EnvVariable>> = vec![];
+
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some((name, value)) if is_identifier(name) => variables.push(Some(EnvVariable {
+                name: name.to_string(),
+                value: value.to_string(),
+            })),
+            _ => {
+                if let Some(variable) = variables.last_mut() {
+                    debug!("[{}] skipping multi-line {:?}", function_name!(), variable);
+                    *variable = None;
+                }
+            }
+        }
+    }
+
+    let variables: Vec<EnvVariable> = variables
+        .into_iter()
+        .flatten()
+        .filter(|variable| !rules.skips(variable))
+        .collect();
+
+    debug_value!(variables);
+    variables
+}
+
+/// Suggests writing the values of `variables` found in the arguments of `needle` as `$NAME`.
+pub fn find_env_variables(
+    variables: &[EnvVariable],
+    needle: &str,
+    paths: &PathNormalizer,
+) -> Vec<Suggestion> {
+    let mut names = vec![];
+    let words: Vec<String> = split_words(needle)
+        .into_iter()
+        .enumerate()
+        .map(|(index, word)| {
+            if index == 0 || word.contains('$') {
+                return word;
+            }
+
+            let unquoted = unquote(&word).unwrap_or_else(|_| word.to_owned());
+            let substituted = substitute(variables, &unquoted).or_else(|| {
+                let normalized = paths.normalize_word(&unquoted);
+                (normalized != unquoted)
+                    .then(|| substitute(variables, &normalized))
+                    .flatten()
+            });
+
+            match substituted {
+                Some((used, substituted)) if substituted.len() < word.len() => {
+                    names.extend(used.into_iter().map(|name| format!("${}", name)));
+                    substituted
+                }
+                _ => word,
+            }
+        })
+        .collect();
+
+    if names.is_empty() {
+        return vec![];
+    }
+
+    names.dedup();
+    vec![Suggestion {
+        kind: SuggestionKind::EnvVariable,
+        shortcut: names.join(", "),
+        rewritten: words.join(" "),
+    }]
+}
+
+/// Replaces the longest values found in `text` with references to their variables, quoting
+/// the result if needed. Paths only match whole components, so `$WORK` can stand for
+/// `/home/me/work` in `/home/me/work/projects` but not in `/home/me/workshop`. Returns the names
+/// used, or `None` when no value was found.
+fn substitute(variables: &[EnvVariable], text: &str) -> Option<(Vec<String>, String)> {
+    let mut used = vec![];
+    let mut parts: Vec<(bool, String)> = vec![];
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let variable = variables
+            .iter()
+            .filter(|variable| {
+                !variable.value.is_empty()
+                    && rest.starts_with(&variable.value)
+                    && ends_path(&variable.value, &rest[variable.value.len()..])
+            })
+            .max_by_key(|variable| variable.value.len());
+
+        match variable {
+            Some(variable) => {
+                rest = &rest[variable.value.len()..];
+                let reference = if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+                    format!("${{{}}}", variable.name)
+                } else {
+                    format!("${}", variable.name)
+                };
+                used.push(variable.name.to_owned());
+                parts.push((true, reference));
+            }
+            None => {
+                rest = &rest[c.len_utf8()..];
+                match parts.last_mut() {
+                    Some((false, literal)) => literal.push(c),
+                    _ => parts.push((false, c.to_string())),
+                }
+            }
+        }
+    }
+
+    if used.is_empty() {
+        return None;
+    }
+
+    let quoted = parts
+        .iter()
+        .any(|(reference, text)| !reference && !text.chars().all(is_plain));
+    let mut substituted: String = parts
+        .into_iter()
+        .map(|(reference, text)| {
+            if reference || !quoted {
+                text
+            } else {
+                text.chars()
+                    .flat_map(|c| {
+                        let escape = "\"\\$`".contains(c).then_some('\\');
+                        escape.into_iter().chain([c])
+                    })
+                    .collect()
+            }
+        })
+        .collect();
+
+    if quoted {
+        substituted = format!("\"{}\"", substituted);
+    }
+
+    Some((used, substituted))
+}
+
+/// Whether a value followed by `after` ends on a path component, when it is a path at all.
+fn ends_path(value: &str, after: &str) -> bool {
+    !value.contains('/')
+        || value.ends_with('/')
+        || after.is_empty()
+        || after.starts_with(|c: char| c == '/' || c == '"' || c == '\'' || c.is_whitespace())
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Characters that can follow a variable reference without quoting.
+fn is_plain(c: char) -> bool {
+    c.is_alphanumeric() || "-_.,:/@%+=".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_env_variables, parse_env_variables, EnvRules};
+    use crate::{Config, PathNormalizer};
+    use std::path::Path;
+
+    #[test]
+    fn it_parses_env_output() {
+        let rules = EnvRules::from_config(&Config::default()).unwrap();
+        let variables = parse_env_variables(
+            "PROJ=/home/me/work/project\nPWD=/home/me/work/project\nK8S_NS=production\nSHORT=abc\nMULTI=first\nsecond line\nDOTFILES=/home/me/.dotfiles\n",
+            &rules,
+        );
+
+        let names: Vec<&str> = variables
+            .iter()
+            .map(|variable| variable.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["PROJ", "K8S_NS", "DOTFILES"]);
+    }
+
+    #[test]
+    fn it_suggests_env_variables() {
+        let rules = EnvRules::default();
+        let variables = parse_env_variables(
+            "WORK=/home/me/work\nPROJ=/home/me/work/project\nK8S_NS=production\n",
+            &rules,
+        );
+        let paths = PathNormalizer::default().with_home(Path::new("/home/me"));
+        let rewritten = |needle: &str| -> Vec<String> {
+            find_env_variables(&variables, needle, &paths)
+                .into_iter()
+                .map(|suggestion| suggestion.rewritten)
+                .collect()
+        };
+
+        assert_eq!(
+            rewritten("kubectl -n production get pods"),
+            vec!["kubectl -n $K8S_NS get pods"]
+        );
+        assert_eq!(
+            rewritten("cd /home/me/work/project/src"),
+            vec!["cd $PROJ/src"]
+        );
+        assert_eq!(rewritten("cd ~/work/project"), vec!["cd $PROJ"]);
+        assert_eq!(
+            rewritten("ls /home/me/work/projects"),
+            vec!["ls $WORK/projects"]
+        );
+        assert_eq!(
+            rewritten("cp '/home/me/work/my notes.md' ."),
+            vec!["cp \"$WORK/my notes.md\" ."]
+        );
+        assert!(rewritten("cd /home/me/workshop").is_empty());
+        assert!(rewritten("echo $PROJ").is_empty());
+        assert!(rewritten("production").is_empty());
+    }
+}
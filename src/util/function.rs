@@ -0,0 +1,307 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use super::suggestion::*;
+
+/// How a function passes its arguments to the command it wraps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arguments {
+    /// The function takes no arguments, like an alias.
+    None,
+    /// `"$@"`: every argument, as separate words.
+    All,
+    /// `"$*"`: every argument, joined into a single word.
+    Joined,
+    /// `"$1" "$2" ...`: exactly this many arguments, in order.
+    Positional(usize),
+}
+
+/// A function whose body is a single command followed by its own arguments,
+/// such as `gcm() { git commit -m "$*"; }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionShortcut {
+    pub name: Name,
+    /// The unquoted words of the command before the arguments.
+    pub command: Vec<String>,
+    pub arguments: Arguments,
+}
+
+impl FunctionShortcut {
+    /// Classifies a function body, returning `None` unless it is a single command with literal
+    /// words followed by the function's arguments.
+    pub fn classify(name: &str, body: &str) -> Option<FunctionShortcut> {
+        debug_value!(name, body);
+        let body = body.trim().trim_end_matches(';').trim_end();
+
+        if body.is_empty() || has_operator(body) {
+            trace!("[{}] {:?} is not a single command", function_name!(), name);
+            return None;
+        }
+
+        let words = split_words(body);
+        let (arguments, literal_count) = match words.last().map(|word| word.as_str()) {
+            Some("\"$@\"" | "$@") => (Arguments::All, words.len() - 1),
+            Some("\"$*\"" | "$*") => (Arguments::Joined, words.len() - 1),
+            _ => {
+                let count = words
+                    .iter()
+                    .rev()
+                    .take_while(|word| positional_index(word).is_some())
+                    .count();
+                let in_order = words[words.len() - count..]
+                    .iter()
+                    .enumerate()
+                    .all(|(index, word)| positional_index(word) == Some(index + 1));
+
+                match (count, in_order) {
+                    (0, _) => (Arguments::None, words.len()),
+                    (count, true) => (Arguments::Positional(count), words.len() - count),
+                    (_, false) => return None,
+                }
+            }
+        };
+
+        let command = words[..literal_count]
+            .iter()
+            .map(|word| {
+                if word.contains(['$', '`']) {
+                    None
+                } else {
+                    unquote(word).ok()
+                }
+            })
+            .collect::<Option<Vec<String>>>()?;
+
+        if command.is_empty() {
+            return None;
+        }
+
+        Some(FunctionShortcut {
+            name: Name::new(name).ok()?,
+            command,
+            arguments,
+        })
+    }
+
+    /// What to type instead of `needle`, if this function can stand in for it.
+    pub fn rewrite(&self, needle: &str) -> Option<String> {
+        let words = split_words(needle);
+        let unquoted: Vec<String> = words
+            .iter()
+            .map(|word| unquote(word).unwrap_or_else(|_| word.to_owned()))
+            .collect();
+
+        if !unquoted.starts_with(&self.command) {
+            return None;
+        }
+
+        let rest = &words[self.command.len()..];
+        let arguments: Vec<String> = match self.arguments {
+            Arguments::None if rest.is_empty() => vec![],
+            Arguments::All => rest.to_vec(),
+            Arguments::Positional(count) if rest.len() == count => rest.to_vec(),
+            // `"$*"` joins the arguments with spaces, so a single quoted word can be typed unquoted.
+            Arguments::Joined if !rest.is_empty() => {
+                let joined = unquoted[self.command.len()..].join(" ");
+                if joined.chars().all(is_plain) {
+                    vec![joined]
+                } else {
+                    rest.to_vec()
+                }
+            }
+            _ => return None,
+        };
+
+        Some(
+            [self.name.get().to_owned()]
+                .into_iter()
+                .chain(arguments)
+                .collect::<Vec<String>>()
+                .join(" "),
+        )
+    }
+}
+
+/// Reads the functions printed by `typeset -f` in zsh or `declare -f` in bash,
+/// keeping the ones that are shortcuts for a single command.
+pub fn parse_functions(output: &str) -> Vec<FunctionShortcut> {
+    let mut functions = vec![];
+    let mut current: Option<(&str, Vec<&str>)> = None;
+
+    for line in output.lines() {
+        match &mut current {
+            None => {
+                let header = line.trim_end();
+                let header = header.strip_suffix('{').unwrap_or(header).trim_end();
+                let Some(name) = header.strip_suffix("()") else {
+                    continue;
+                };
+                current = Some((name.trim(), vec![]));
+            }
+            // bash prints the opening brace on its own line.
+            Some((_, body)) if body.is_empty() && line.trim() == "{" => {}
+            Some((name, body)) if line.trim_end() == "}" => {
+                let body = body.join("\n");
+                match FunctionShortcut::classify(name, &body) {
+                    Some(function) => functions.push(function),
+                    None => debug!("[{}] {:?} is not a shortcut", function_name!(), name),
+                }
+                current = None;
+            }
+            Some((_, body)) => body.push(line),
+        }
+    }
+
+    debug_value!(functions);
+    functions
+}
+
+/// The names of all the functions printed by `typeset -f` or `declare -f`, shortcuts or not.
+pub fn parse_function_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let header = line.trim_end();
+            let header = header.strip_suffix('{').unwrap_or(header).trim_end();
+            header
+                .strip_suffix("()")
+                .map(|name| name.trim().to_string())
+        })
+        .collect()
+}
+
+/// Suggests the functions that could have been typed instead of `needle`.
+pub fn find_function_shortcuts(functions: &[FunctionShortcut], needle: &str) -> Vec<Suggestion> {
+    functions
+        .iter()
+        .filter_map(|function| {
+            Some(Suggestion {
+                kind: SuggestionKind::Function,
+                shortcut: function.name.get().to_owned(),
+                rewritten: function.rewrite(needle)?,
+            })
+        })
+        .collect()
+}
+
+/// Whether the text contains a command separator, a pipe or a redirection outside of quotes.
+fn has_operator(text: &str) -> bool {
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ';' | '&' | '|' | '<' | '>' | '(' | ')' | '\n' | '`') => return true,
+            (None, _) => {}
+        }
+    }
+
+    false
+}
+
+/// The argument a word refers to, for `$1`, `"$1"` and `"${1}"`.
+fn positional_index(word: &str) -> Option<usize> {
+    let word = word
+        .strip_prefix('"')
+        .and_then(|w| w.strip_suffix('"'))
+        .unwrap_or(word);
+    let word = word.strip_prefix('$')?;
+    let word = word
+        .strip_prefix('{')
+        .and_then(|w| w.strip_suffix('}'))
+        .unwrap_or(word);
+
+    word.parse().ok().filter(|&index| index > 0)
+}
+
+/// Characters that mean the same thing to the shell whether they are quoted or not.
+fn is_plain(c: char) -> bool {
+    c.is_alphanumeric() || " -_.,:/@%+=".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_function_shortcuts, parse_function_names, parse_functions, Arguments, FunctionShortcut,
+    };
+
+    #[test]
+    fn it_classifies_functions() {
+        let gcm = FunctionShortcut::classify("gcm", "git commit -m \"$*\";").unwrap();
+        assert_eq!(gcm.command, vec!["git", "commit", "-m"]);
+        assert_eq!(gcm.arguments, Arguments::Joined);
+
+        let all = FunctionShortcut::classify("d", "docker \"$@\"").unwrap();
+        assert_eq!(all.arguments, Arguments::All);
+
+        let two = FunctionShortcut::classify("cpx", "cp -r \"$1\" \"${2}\"").unwrap();
+        assert_eq!(two.arguments, Arguments::Positional(2));
+
+        let none = FunctionShortcut::classify("gst", "git status").unwrap();
+        assert_eq!(none.arguments, Arguments::None);
+
+        assert_eq!(
+            FunctionShortcut::classify("mkcd", "mkdir -p \"$1\" && cd \"$1\""),
+            None
+        );
+        assert_eq!(FunctionShortcut::classify("swap", "mv \"$2\" \"$1\""), None);
+        assert_eq!(FunctionShortcut::classify("e", "$EDITOR \"$@\""), None);
+        assert_eq!(FunctionShortcut::classify("l", "ls | less"), None);
+    }
+
+    #[test]
+    fn it_parses_typeset_and_declare_output() {
+        let zsh =
+            "gcm () {\n\tgit commit -m \"$*\"\n}\nmkcd () {\n\tmkdir -p \"$1\" && cd \"$1\"\n}\n";
+        let bash = "gcm () \n{ \n    git commit -m \"$*\"\n}\nd () \n{ \n    docker \"$@\"\n}\n";
+
+        let names = |functions: Vec<FunctionShortcut>| -> Vec<String> {
+            functions
+                .iter()
+                .map(|function| function.name.to_string())
+                .collect()
+        };
+
+        assert_eq!(names(parse_functions(zsh)), vec!["gcm"]);
+        assert_eq!(names(parse_functions(bash)), vec!["gcm", "d"]);
+        assert_eq!(parse_function_names(zsh), vec!["gcm", "mkcd"]);
+        assert_eq!(parse_function_names(bash), vec!["gcm", "d"]);
+    }
+
+    #[test]
+    fn it_rewrites_commands_with_functions() {
+        let functions = parse_functions(
+            "gcm () {\n\tgit commit -m \"$*\"\n}\ncpx () {\n\tcp -r \"$1\" \"$2\"\n}\nd () {\n\tdocker \"$@\"\n}\n",
+        );
+        let rewritten = |needle: &str| -> Vec<String> {
+            find_function_shortcuts(&functions, needle)
+                .into_iter()
+                .map(|suggestion| suggestion.rewritten)
+                .collect()
+        };
+
+        assert_eq!(
+            rewritten("git commit -m \"fix typo\""),
+            vec!["gcm fix typo"]
+        );
+        assert_eq!(
+            rewritten("git commit -m 'it''s $HOME'"),
+            vec!["gcm 'it''s $HOME'"]
+        );
+        assert_eq!(
+            rewritten("cp -r src 'dest dir'"),
+            vec!["cpx src 'dest dir'"]
+        );
+        assert!(rewritten("cp -r src").is_empty());
+        assert_eq!(rewritten("docker ps -a"), vec!["d ps -a"]);
+        assert!(rewritten("git commit").is_empty());
+    }
+}
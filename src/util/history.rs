@@ -0,0 +1,583 @@
+use super::log::*;
+use super::macros::*;
+use super::shell::Shell;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OpenFlags};
+use std::{
+    env, fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+    vec,
+};
+
+/// How many Atuin entries are read from the database at a time.
+const ATUIN_PAGE_SIZE: usize = 1000;
+
+/// A command from the history, with what the history format records about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub command: String,
+    /// The directory the command ran in, which only Atuin records.
+    pub cwd: Option<PathBuf>,
+}
+
+/// The ways shell history is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// `~/.zsh_history`, with or without the `: <time>:<duration>;` prefix of `EXTENDED_HISTORY`.
+    Zsh,
+    /// `~/.bash_history`, with the `#<time>` lines `HISTTIMEFORMAT` adds, if set.
+    Bash,
+    /// fish's `fish_history`, a list of `- cmd:` entries.
+    Fish,
+    /// Atuin's SQLite database.
+    Atuin,
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+/// The entries of a history, oldest first, read as they are iterated over: files line by line,
+/// and Atuin's database a page at a time.
+pub struct History {
+    entries: Box<dyn Iterator<Item = HistoryEntry>>,
+}
+
+/// Reads Atuin's history in pages ordered by time, each starting after the last entry read.
+struct AtuinEntries {
+    connection: Connection,
+    /// The timestamp and id of the last entry read.
+    last: Option<(i64, String)>,
+    page: vec::IntoIter<(i64, String, HistoryEntry)>,
+    done: bool,
+}
+
+/// The lines of a history file as text, with zsh's metafied bytes restored when `unmetafy` is set.
+struct Lines<R> {
+    reader: R,
+    unmetafy: bool,
+}
+
+impl History {
+    /// Opens the history at `path`, guessing its format from the path and content when `format` is `None`.
+    pub fn open(path: &Path, format: Option<HistoryFormat>) -> Result<History, HistoryError> {
+        let format = match format {
+            Some(format) => format,
+            None => HistoryFormat::detect(path)?,
+        };
+        debug_value!(path, format);
+
+        let entries: Box<dyn Iterator<Item = HistoryEntry>> = match format {
+            HistoryFormat::Atuin => {
+                Box::new(AtuinEntries::open(path).map_err(HistoryError::Sqlite)?)
+            }
+            format => {
+                let file = File::open(path).map_err(HistoryError::Io)?;
+                format.parse(BufReader::new(file))
+            }
+        };
+
+        Ok(History { entries })
+    }
+}
+
+impl fmt::Debug for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History").finish_non_exhaustive()
+    }
+}
+
+impl Iterator for History {
+    type Item = HistoryEntry;
+
+    fn next(&mut self) -> Option<HistoryEntry> {
+        self.entries.next()
+    }
+}
+
+impl HistoryFormat {
+    /// Recognizes Atuin databases and fish histories by name, and zsh's extended format by its first line.
+    pub fn detect(path: &Path) -> Result<HistoryFormat, HistoryError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if path.extension().is_some_and(|extension| extension == "db") {
+            return Ok(HistoryFormat::Atuin);
+        }
+        if name.contains("fish") {
+            return Ok(HistoryFormat::Fish);
+        }
+        if name.contains("zsh") || name.contains("zhistory") {
+            return Ok(HistoryFormat::Zsh);
+        }
+
+        let mut first = vec![];
+        BufReader::new(File::open(path).map_err(HistoryError::Io)?)
+            .read_until(b'\n', &mut first)
+            .map_err(HistoryError::Io)?;
+        let format = if strip_extended_prefix(&String::from_utf8_lossy(&first))
+            .0
+            .is_some()
+        {
+            HistoryFormat::Zsh
+        } else {
+            HistoryFormat::Bash
+        };
+
+        Ok(format)
+    }
+
+    /// Parses a history file as it is read; Atuin histories are databases and have no entries here.
+    pub fn parse<'a>(
+        &self,
+        reader: impl BufRead + 'a,
+    ) -> Box<dyn Iterator<Item = HistoryEntry> + 'a> {
+        let lines = Lines {
+            reader,
+            unmetafy: *self == HistoryFormat::Zsh,
+        };
+
+        let entries: Box<dyn Iterator<Item = HistoryEntry>> = match self {
+            HistoryFormat::Zsh => Box::new(parse_zsh(lines)),
+            HistoryFormat::Bash => Box::new(parse_bash(lines)),
+            HistoryFormat::Fish => Box::new(parse_fish(lines)),
+            HistoryFormat::Atuin => Box::new(iter::empty()),
+        };
+        Box::new(entries.filter(|entry| !entry.command.trim().is_empty()))
+    }
+}
+
+/// The history file the shell writes to: `$HISTFILE` when it is exported, or the shell's default.
+pub fn default_history_file(shell: Shell) -> Option<PathBuf> {
+    if let Some(path) = env::var_os("HISTFILE").filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+
+    let home = PathBuf::from(env::var_os("HOME")?);
+    match shell {
+        Shell::Zsh => Some(home.join(".zsh_history")),
+        Shell::Bash => Some(home.join(".bash_history")),
+        Shell::Fish => Some(home.join(".local/share/fish/fish_history")),
+        Shell::Nu => None,
+    }
+}
+
+/// zsh escapes the bytes it uses internally as a `0x83` byte followed by the original byte xor `0x20`.
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    const META: u8 = 0x83;
+    let mut bytes = content.iter();
+    let mut unmetafied = Vec::with_capacity(content.len());
+
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            META => unmetafied.extend(bytes.next().map(|byte| byte ^ 0x20)),
+            byte => unmetafied.push(byte),
+        }
+    }
+
+    unmetafied
+}
+
+/// Lines ending with `\` are joined with the next one, as zsh writes multi-line commands.
+fn parse_zsh(mut lines: impl Iterator<Item = String>) -> impl Iterator<Item = HistoryEntry> {
+    iter::from_fn(move || {
+        let line = lines.next()?;
+        let (timestamp, command) = strip_extended_prefix(&line);
+        let mut entry = HistoryEntry {
+            timestamp: timestamp.and_then(|seconds| DateTime::from_timestamp(seconds, 0)),
+            command: command.to_string(),
+            cwd: None,
+        };
+
+        while entry.command.ends_with('\\') {
+            entry.command.pop();
+            let Some(line) = lines.next() else {
+                break;
+            };
+            entry.command = entry.command + "\n" + &line;
+        }
+
+        Some(entry)
+    })
+}
+
+/// Without timestamps every line is a command; with them, a command runs until the next timestamp.
+fn parse_bash(lines: impl Iterator<Item = String>) -> impl Iterator<Item = HistoryEntry> {
+    let mut lines = lines.peekable();
+
+    iter::from_fn(move || {
+        let line = lines.next()?;
+        let Some(seconds) = bash_timestamp(&line) else {
+            return Some(HistoryEntry {
+                timestamp: None,
+                command: line,
+                cwd: None,
+            });
+        };
+
+        let mut command: Vec<String> = vec![];
+        while let Some(line) = lines.next_if(|line| bash_timestamp(line).is_none()) {
+            command.push(line);
+        }
+
+        Some(HistoryEntry {
+            timestamp: DateTime::from_timestamp(seconds, 0),
+            command: command.join("\n"),
+            cwd: None,
+        })
+    })
+}
+
+/// Reads the `cmd` and `when` fields of each entry, ignoring the `paths` fish lists under them.
+fn parse_fish(lines: impl Iterator<Item = String>) -> impl Iterator<Item = HistoryEntry> {
+    let mut lines = lines.peekable();
+
+    iter::from_fn(move || {
+        let command = loop {
+            if let Some(command) = lines.next()?.strip_prefix("- cmd: ") {
+                break unescape_fish(command);
+            }
+        };
+        let mut entry = HistoryEntry {
+            timestamp: None,
+            command,
+            cwd: None,
+        };
+
+        while let Some(line) = lines.next_if(|line| !line.starts_with("- cmd: ")) {
+            if let Some(when) = line.trim_start().strip_prefix("when: ") {
+                entry.timestamp = when
+                    .trim()
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| DateTime::from_timestamp(seconds, 0));
+            }
+        }
+
+        Some(entry)
+    })
+}
+
+/// fish writes newlines as `\n` and backslashes as `\\`.
+fn unescape_fish(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                unescaped.push('\n');
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                unescaped.push('\\');
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+impl AtuinEntries {
+    fn open(path: &Path) -> Result<AtuinEntries, rusqlite::Error> {
+        Ok(AtuinEntries {
+            connection: Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?,
+            last: None,
+            page: vec![].into_iter(),
+            done: false,
+        })
+    }
+
+    /// Atuin stores timestamps in nanoseconds, and keeps deleted commands with a `deleted_at` time.
+    fn read_page(&self) -> Result<Vec<(i64, String, HistoryEntry)>, rusqlite::Error> {
+        let (timestamp, id) = self.last.to_owned().unwrap_or((i64::MIN, String::new()));
+        let mut statement = self.connection.prepare_cached(
+            "SELECT timestamp, id, command, cwd FROM history \
+             WHERE deleted_at IS NULL AND (timestamp, id) > (?1, ?2) \
+             ORDER BY timestamp, id LIMIT ?3",
+        )?;
+
+        let rows = statement
+            .query_map((timestamp, id, ATUIN_PAGE_SIZE as i64), |row| {
+                let timestamp: i64 = row.get(0)?;
+                let cwd: Option<String> = row.get(3)?;
+                let entry = HistoryEntry {
+                    timestamp: Some(DateTime::from_timestamp_nanos(timestamp)),
+                    command: row.get(2)?,
+                    cwd: cwd.filter(|cwd| !cwd.is_empty()).map(PathBuf::from),
+                };
+                Ok((timestamp, row.get(1)?, entry))
+            })?
+            .collect();
+        rows
+    }
+}
+
+impl Iterator for AtuinEntries {
+    type Item = HistoryEntry;
+
+    fn next(&mut self) -> Option<HistoryEntry> {
+        if let Some((timestamp, id, entry)) = self.page.next() {
+            self.last = Some((timestamp, id));
+            return Some(entry);
+        }
+        if self.done {
+            return None;
+        }
+
+        let page = self.read_page().unwrap_or_else(|err| {
+            debug!("[{}] stopped reading history: {}", function_name!(), err);
+            vec![]
+        });
+        self.done = page.len() < ATUIN_PAGE_SIZE;
+        self.page = page.into_iter();
+        let (timestamp, id, entry) = self.page.next()?;
+        self.last = Some((timestamp, id));
+        Some(entry)
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut line = vec![];
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) => {
+                debug!("[{}] stopped reading history: {}", function_name!(), err);
+                return None;
+            }
+        }
+
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+        if self.unmetafy {
+            line = unmetafy(&line);
+        }
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+/// The `#1700000000` lines bash writes before each command when `HISTTIMEFORMAT` is set.
+fn bash_timestamp(line: &str) -> Option<i64> {
+    let time = line.strip_prefix('#')?;
+    if time.is_empty() || !time.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    time.parse().ok()
+}
+
+/// Splits the `: <time>:<duration>;` prefix off a zsh history line, returning the start time.
+fn strip_extended_prefix(line: &str) -> (Option<i64>, &str) {
+    let Some(rest) = line.strip_prefix(": ") else {
+        return (None, line);
+    };
+
+    match rest.split_once(';') {
+        Some((time, command))
+            if time
+                .split(':')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())) =>
+        {
+            let start = time.split(':').next().and_then(|start| start.parse().ok());
+            (start, command)
+        }
+        _ => (None, line),
+    }
+}
+
+impl FromStr for HistoryFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "zsh" => Ok(HistoryFormat::Zsh),
+            "bash" => Ok(HistoryFormat::Bash),
+            "fish" => Ok(HistoryFormat::Fish),
+            "atuin" => Ok(HistoryFormat::Atuin),
+            _ => Err(format!(
+                "unsupported history format {:?}, expected one of: zsh, bash, fish, atuin",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for HistoryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HistoryFormat::Zsh => "zsh",
+            HistoryFormat::Bash => "bash",
+            HistoryFormat::Fish => "fish",
+            HistoryFormat::Atuin => "atuin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Io(err) => write!(f, "{}", err),
+            HistoryError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, HistoryEntry, HistoryFormat, ATUIN_PAGE_SIZE};
+    use chrono::DateTime;
+    use rusqlite::Connection;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/history")
+            .join(name)
+    }
+
+    fn commands(history: History) -> Vec<String> {
+        history.map(|entry| entry.command).collect()
+    }
+
+    #[test]
+    fn it_reads_zsh_history() {
+        let path = fixture("zsh_history");
+        assert_eq!(HistoryFormat::detect(&path).unwrap(), HistoryFormat::Zsh);
+
+        let entries: Vec<HistoryEntry> = History::open(&path, None).unwrap().collect();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.command.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "git status",
+                "echo 'héllo → ünïcode'",
+                "for f in *\ndo echo $f\ndone",
+                "ls -la"
+            ]
+        );
+        assert_eq!(
+            entries[0].timestamp,
+            DateTime::from_timestamp(1700000000, 0)
+        );
+        assert_eq!(entries[3].timestamp, None);
+    }
+
+    #[test]
+    fn it_reads_bash_history() {
+        assert_eq!(
+            commands(History::open(&fixture("bash_history"), None).unwrap()),
+            vec!["git status", "ls -la"]
+        );
+
+        let entries: Vec<HistoryEntry> = History::open(
+            &fixture("bash_history_timestamped"),
+            Some(HistoryFormat::Bash),
+        )
+        .unwrap()
+        .collect();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.command.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["git status", "cat <<EOF\nmulti-line\nEOF"]
+        );
+        assert_eq!(
+            entries[1].timestamp,
+            DateTime::from_timestamp(1700000060, 0)
+        );
+    }
+
+    #[test]
+    fn it_reads_fish_history() {
+        let entries: Vec<HistoryEntry> = History::open(&fixture("fish_history"), None)
+            .unwrap()
+            .collect();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.command.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["git status", "echo one\necho two", "printf 'a\\\\b'"]
+        );
+        assert_eq!(
+            entries[0].timestamp,
+            DateTime::from_timestamp(1700000000, 0)
+        );
+    }
+
+    #[test]
+    fn it_reads_atuin_history() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.db");
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(include_str!("../../tests/fixtures/history/atuin.sql"))
+            .unwrap();
+        drop(connection);
+
+        let entries: Vec<HistoryEntry> = History::open(&path, None).unwrap().collect();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.command.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["git status", "cargo test"]
+        );
+        assert_eq!(entries[1].cwd, Some(PathBuf::from("/home/me/work/crate")));
+        assert_eq!(
+            entries[1].timestamp,
+            DateTime::from_timestamp(1700000060, 0)
+        );
+    }
+
+    #[test]
+    fn it_reads_atuin_history_across_pages() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.db");
+        let mut connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(include_str!("../../tests/fixtures/history/atuin.sql"))
+            .unwrap();
+        let transaction = connection.transaction().unwrap();
+        for index in 0..2 * ATUIN_PAGE_SIZE {
+            // Entries sharing a timestamp are told apart by their id.
+            transaction
+                .execute(
+                    "INSERT INTO history VALUES (?1, 1700000030000000000, 0, 0, 'ls', '', 's1', 'host:me', NULL)",
+                    [format!("p{:05}", index)],
+                )
+                .unwrap();
+        }
+        transaction.commit().unwrap();
+        drop(connection);
+
+        let commands = commands(History::open(&path, None).unwrap());
+        assert_eq!(commands.len(), 2 * ATUIN_PAGE_SIZE + 2);
+        assert_eq!(commands.first().map(String::as_str), Some("git status"));
+        assert_eq!(commands.last().map(String::as_str), Some("cargo test"));
+    }
+}
@@ -0,0 +1,298 @@
+use super::alias::*;
+use super::config::*;
+use super::log::*;
+use super::macros::*;
+use fancy_regex::Regex;
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The comment that marks an alias definition as ignored.
+pub const IGNORE_MARKER: &str = "alias-helper: ignore";
+
+/// A rule that keeps an alias, or a typed command, out of the results.
+#[derive(Debug, Clone)]
+pub enum IgnoreRule {
+    /// Matches alias names with a glob, where `*` and `?` are wildcards.
+    Alias(Regex),
+    /// Matches typed commands with a glob, either whole or up to a word boundary.
+    CommandGlob(Regex),
+    /// Matches typed commands with a regex, anywhere in the command.
+    CommandRegex(Regex),
+    /// Matches every alias read from the given file.
+    Source(PathBuf),
+}
+
+/// The set of ignore rules [crate::find_alias_with_options] applies before returning its results.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRule {
+    /// Builds a rule ignoring aliases whose name matches `glob`.
+    pub fn alias(glob: &str) -> Result<IgnoreRule, Box<fancy_regex::Error>> {
+        Ok(IgnoreRule::Alias(Regex::new(&format!(
+            "^{}$",
+            glob_to_regex(glob)
+        ))?))
+    }
+
+    /// Builds a rule ignoring typed commands that match `glob`, or start with a match followed by more words.
+    pub fn command_glob(glob: &str) -> Result<IgnoreRule, Box<fancy_regex::Error>> {
+        Ok(IgnoreRule::CommandGlob(Regex::new(&format!(
+            "^{}(\\s|$)",
+            glob_to_regex(glob.trim())
+        ))?))
+    }
+
+    /// Builds a rule ignoring typed commands that `pattern` finds a match in.
+    pub fn command_regex(pattern: &str) -> Result<IgnoreRule, Box<fancy_regex::Error>> {
+        Ok(IgnoreRule::CommandRegex(Regex::new(pattern)?))
+    }
+
+    /// Builds a rule ignoring the aliases read from the file at `path`.
+    pub fn source(path: &Path) -> IgnoreRule {
+        IgnoreRule::Source(fs::canonicalize(path).unwrap_or_else(|_| path.to_owned()))
+    }
+}
+
+impl IgnoreRules {
+    pub fn new(rules: Vec<IgnoreRule>) -> IgnoreRules {
+        IgnoreRules { rules }
+    }
+
+    /// Builds the rules from the `ignored_*` configuration keys.
+    pub fn from_config(config: &Config) -> Result<IgnoreRules, ConfigError> {
+        let invalid =
+            |key: &str| ConfigError::InvalidValue(key.to_string(), config.source(key).to_owned());
+        let mut rules = vec![];
+
+        for glob in &config.ignored_aliases {
+            rules.push(IgnoreRule::alias(glob).map_err(|_| invalid("ignored_aliases"))?);
+        }
+
+        for glob in &config.ignored_commands {
+            rules.push(IgnoreRule::command_glob(glob).map_err(|_| invalid("ignored_commands"))?);
+        }
+
+        for pattern in &config.ignored_command_patterns {
+            rules.push(
+                IgnoreRule::command_regex(pattern)
+                    .map_err(|_| invalid("ignored_command_patterns"))?,
+            );
+        }
+
+        for path in &config.ignored_sources {
+            rules.push(IgnoreRule::source(&expand_home(path)));
+        }
+
+        debug_value!(rules);
+        Ok(IgnoreRules { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether `alias` should never be returned as a match, including when its definition carries
+    /// an [IGNORE_MARKER].
+    pub fn ignores_alias(&self, alias: &Alias) -> bool {
+        let origin = alias.origin.as_ref();
+        if origin.is_some_and(|origin| origin.marked) {
+            return true;
+        }
+
+        self.rules.iter().any(|rule| match rule {
+            IgnoreRule::Alias(regex) => is_match(regex, alias.name.get()),
+            IgnoreRule::Source(path) => {
+                origin
+                    .and_then(|origin| origin.file.as_ref())
+                    .is_some_and(|file| {
+                        file == path || fs::canonicalize(file).is_ok_and(|file| file == *path)
+                    })
+            }
+            IgnoreRule::CommandGlob(_) | IgnoreRule::CommandRegex(_) => false,
+        })
+    }
+
+    /// Whether the typed `command` is meant to be typed in full, and should never match.
+    pub fn ignores_command(&self, command: &str) -> bool {
+        let command = command.trim();
+
+        self.rules.iter().any(|rule| match rule {
+            IgnoreRule::CommandGlob(regex) | IgnoreRule::CommandRegex(regex) => {
+                is_match(regex, command)
+            }
+            IgnoreRule::Alias(_) | IgnoreRule::Source(_) => false,
+        })
+    }
+}
+
+fn is_match(regex: &Regex, input: &str) -> bool {
+    regex.is_match(input).unwrap_or_else(|err| {
+        ErrorCode::RegexValidationMatch(regex, input, err).log_debug(function_name!());
+        false
+    })
+}
+
+/// The lines of `code` whose alias definitions are marked with an [IGNORE_MARKER] comment,
+/// counting from 1. The marker is either at the end of the line, or on a comment line right above.
+pub fn marked_lines(code: &str) -> HashSet<usize> {
+    let mut lines = HashSet::new();
+
+    for (index, line) in code.lines().enumerate() {
+        let has_marker = line
+            .rsplit_once('#')
+            .is_some_and(|(_, comment)| comment.trim() == IGNORE_MARKER);
+
+        if has_marker && line.trim_start().starts_with('#') {
+            lines.insert(index + 2);
+        } else if has_marker {
+            lines.insert(index + 1);
+        }
+    }
+
+    lines
+}
+
+/// Converts a glob, where `*` and `?` are wildcards, into an unanchored regex.
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&fancy_regex::escape(&c.to_string())),
+        }
+    }
+
+    regex
+}
+
+/// Replaces a leading `~` with the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(format!("{}{}", home.to_string_lossy(), rest))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IgnoreRule, IgnoreRules};
+    use crate::{Alias, RcReader};
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use tempfile::TempDir;
+
+    fn write_rc(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn it_ignores_aliases_by_name_glob() {
+        let rules = IgnoreRules::new(vec![
+            IgnoreRule::alias("gb").unwrap(),
+            IgnoreRule::alias("k*").unwrap(),
+        ]);
+
+        assert!(rules.ignores_alias(&Alias::from("gb='git branch'").unwrap()));
+        assert!(rules.ignores_alias(&Alias::from("kctx='kubectl config'").unwrap()));
+        assert!(!rules.ignores_alias(&Alias::from("gba='git branch --all'").unwrap()));
+    }
+
+    #[test]
+    fn it_ignores_commands_by_glob_and_regex() {
+        let rules = IgnoreRules::new(vec![
+            IgnoreRule::command_glob("git push --force").unwrap(),
+            IgnoreRule::command_glob("rm -rf *").unwrap(),
+            IgnoreRule::command_regex("--no-verify").unwrap(),
+        ]);
+
+        assert!(rules.ignores_command("git push --force"));
+        assert!(rules.ignores_command("git push --force origin main"));
+        assert!(!rules.ignores_command("git push --force-with-lease"));
+        assert!(rules.ignores_command("rm -rf build"));
+        assert!(rules.ignores_command("git commit --no-verify -m wip"));
+        assert!(!rules.ignores_command("git push origin"));
+    }
+
+    #[test]
+    fn it_ignores_aliases_from_a_source_file() {
+        let dir = TempDir::new().unwrap();
+        let plugin = write_rc(
+            dir.path(),
+            "plugin.zsh",
+            "alias gp='git push'\n  alias -g G='| grep'\nfunction x() {}\n",
+        );
+        let rc = write_rc(
+            dir.path(),
+            "sourcing.zshrc",
+            &format!("source {}\nalias gb='git branch'\n", plugin.display()),
+        );
+        let rules = IgnoreRules::new(vec![IgnoreRule::source(&plugin)]);
+
+        let ignored: Vec<(String, bool)> = RcReader::read_files(&[rc])
+            .aliases
+            .iter()
+            .map(|alias| (alias.name.to_string(), rules.ignores_alias(alias)))
+            .collect();
+        assert_eq!(
+            ignored,
+            vec![
+                ("gp".to_string(), true),
+                ("G".to_string(), true),
+                ("gb".to_string(), false),
+            ]
+        );
+        assert!(!rules.ignores_alias(&Alias::from("gp='git push'").unwrap()));
+    }
+
+    #[test]
+    fn it_ignores_aliases_with_inline_markers() {
+        let dir = TempDir::new().unwrap();
+        let sourced = write_rc(
+            dir.path(),
+            "marked.zsh",
+            "# alias-helper: ignore\nalias gl='git pull'\nalias gf='git fetch'\n",
+        );
+        let rc = write_rc(
+            dir.path(),
+            "zshrc",
+            &format!(
+                "alias gp='git push' # alias-helper: ignore\n\
+                 alias gb='git branch' # branches\n\
+                 source {}\n\
+                 alias fish-style 'echo hi'\n",
+                sourced.display()
+            ),
+        );
+        let rules = IgnoreRules::default();
+
+        let ignored: Vec<(String, bool)> = RcReader::read_files(&[rc])
+            .aliases
+            .iter()
+            .map(|alias| (alias.name.to_string(), rules.ignores_alias(alias)))
+            .collect();
+        assert_eq!(
+            ignored,
+            vec![
+                ("gp".to_string(), true),
+                ("gb".to_string(), false),
+                ("gl".to_string(), true),
+                ("gf".to_string(), false),
+                ("fish-style".to_string(), false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use super::shell::Shell;
+use std::io::BufRead;
+
+/// An input line that could not be turned into an alias.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedLine {
+    /// The line number, counting from 1.
+    pub line: usize,
+    pub text: String,
+    pub error: AliasError,
+}
+
+/// The aliases read from `alias` output, and the lines that were rejected.
+#[derive(Debug, Clone, Default)]
+pub struct AliasInput {
+    pub aliases: Vec<Alias>,
+    pub rejected: Vec<RejectedLine>,
+}
+
+/// Reads one alias per definition, as printed by `shell`'s `alias` builtin.
+///
+/// A definition whose quotes are still open at the end of a line continues on the next one,
+/// unless that line is a complete definition of its own, in which case the quote was never closed.
+/// Lines that are not valid UTF-8 are rejected like any other unparsable line,
+/// and blank lines are skipped.
+pub fn read_aliases(reader: impl BufRead, shell: Shell) -> AliasInput {
+    let mut input = AliasInput::default();
+    // The definition read so far and the line it started on.
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, bytes) in reader.split(b'\n').enumerate() {
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                debug!("[{}] stopped reading input: {}", function_name!(), err);
+                break;
+            }
+        };
+
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(err) => {
+                let text = String::from_utf8_lossy(err.as_bytes()).into_owned();
+                let (line, text) = match pending.take() {
+                    Some((line, previous)) => (line, format!("{}\n{}", previous, text)),
+                    None => (index + 1, text),
+                };
+                input.reject(line, text.to_owned(), AliasError::ParseError(text));
+                continue;
+            }
+        };
+        let text = text.strip_suffix('\r').unwrap_or(&text);
+
+        if pending.is_some() && shell.is_complete(text) && shell.parse_alias(text).is_ok() {
+            if let Some((line, previous)) = pending.take() {
+                debug!(
+                    "[{}] quote opened on line {} is never closed",
+                    function_name!(),
+                    line
+                );
+                input.reject(line, previous.to_owned(), AliasError::ParseError(previous));
+            }
+        }
+
+        let (line, text) = match pending.take() {
+            Some((line, previous)) => (line, format!("{}\n{}", previous, text)),
+            None => (index + 1, text.to_string()),
+        };
+
+        if !shell.is_complete(&text) {
+            trace!("[{}] line {} continues", function_name!(), line);
+            pending = Some((line, text));
+            continue;
+        }
+
+        match shell.parse_alias(&text) {
+            Ok(alias) => input
+                .aliases
+                .push(alias.with_origin(Origin::stdin(line, &text))),
+            Err(_) if text.trim().is_empty() => {}
+            Err(error) => input.reject(line, text, error),
+        }
+    }
+
+    if let Some((line, text)) = pending {
+        input.reject(line, text.to_owned(), AliasError::ParseError(text));
+    }
+
+    debug_value!(input);
+    input
+}
+
+impl AliasInput {
+    fn reject(&mut self, line: usize, text: String, error: AliasError) {
+        self.rejected.push(RejectedLine { line, text, error });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_aliases, RejectedLine};
+    use crate::{Alias, AliasError, Shell};
+
+    #[test]
+    fn it_collects_rejected_lines() {
+        let input = b"g='git'\n\nbroken\nbad name='x'\n\xff\xfe='x'\ngb='git branch'\n";
+        let input = read_aliases(&input[..], Shell::Zsh);
+
+        assert_eq!(
+            input.aliases,
+            vec![
+                Alias::from("g='git'").unwrap(),
+                Alias::from("gb='git branch'").unwrap(),
+            ]
+        );
+        assert_eq!(
+            input.rejected,
+            vec![
+                RejectedLine {
+                    line: 3,
+                    text: "broken".to_string(),
+                    error: AliasError::ParseError("broken".to_string()),
+                },
+                RejectedLine {
+                    line: 4,
+                    text: "bad name='x'".to_string(),
+                    error: AliasError::IllegalNameChar("bad name".to_string(), ' '),
+                },
+                RejectedLine {
+                    line: 5,
+                    text: "\u{fffd}\u{fffd}='x'".to_string(),
+                    error: AliasError::ParseError("\u{fffd}\u{fffd}='x'".to_string()),
+                },
+            ]
+        );
+        assert_eq!(input.aliases[1].origin.as_ref().unwrap().line, 6);
+    }
+
+    #[test]
+    fn it_keeps_multi_line_definitions_whole() {
+        let input =
+            b"banner='echo one\ntwo'\ng='git'\nheredoc=$'cat <<EOF\\nx\nEOF'\nopen='never closed\n";
+        let input = read_aliases(&input[..], Shell::Zsh);
+
+        assert_eq!(
+            input.aliases,
+            vec![
+                Alias::from("banner='echo one\ntwo'").unwrap(),
+                Alias::from("g='git'").unwrap(),
+                Alias::from("heredoc='cat <<EOF\nx\nEOF'").unwrap(),
+            ]
+        );
+        let lines: Vec<usize> = input
+            .aliases
+            .iter()
+            .map(|alias| alias.origin.as_ref().unwrap().line)
+            .collect();
+        assert_eq!(lines, vec![1, 3, 4]);
+        assert_eq!(input.rejected.len(), 1);
+        assert_eq!(input.rejected[0].line, 6);
+    }
+
+    #[test]
+    fn it_resumes_after_a_quote_that_is_never_closed() {
+        let input = b"broken='echo oops\ng='git'\ngb='git branch'\n";
+        let input = read_aliases(&input[..], Shell::Zsh);
+
+        assert_eq!(
+            input.aliases,
+            vec![
+                Alias::from("g='git'").unwrap(),
+                Alias::from("gb='git branch'").unwrap(),
+            ]
+        );
+        assert_eq!(
+            input.rejected,
+            vec![RejectedLine {
+                line: 1,
+                text: "broken='echo oops".to_string(),
+                error: AliasError::ParseError("broken='echo oops".to_string()),
+            }]
+        );
+        assert_eq!(input.aliases[0].origin.as_ref().unwrap().line, 2);
+    }
+}
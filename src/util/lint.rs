@@ -0,0 +1,468 @@
+use super::alias::*;
+use super::broken::CommandLookup;
+use super::input::RejectedLine;
+use super::log::*;
+use super::macros::*;
+use super::naming::NameGenerator;
+use super::rcfile::{SkipReason, SkippedLine};
+use super::shell::Shell;
+use super::validation;
+use crate::{find_alias_with_options, FindOptions};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::Path,
+};
+
+/// How serious a lint issue is; only errors fail `alias-helper lint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The check that found an issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// Aliases whose commands lead back to themselves.
+    Cycle,
+    /// An alias that expands to nothing.
+    EmptyBody,
+    /// A name the shell doesn't accept.
+    InvalidName,
+    /// A definition that a later one with the same name replaces.
+    Overridden,
+    /// Aliases with different names for the same command.
+    DuplicateCommand,
+    /// An alias that is never suggested for its own command.
+    Unreachable,
+    /// An alias named like an executable on `$PATH` or a builtin of the shell.
+    Shadowing,
+    /// An alias whose command isn't installed.
+    Broken,
+    /// An unterminated quote in a definition or its command.
+    Quoting,
+    /// A definition that can't be parsed for another reason.
+    Syntax,
+}
+
+/// A problem found in an alias definition.
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub rule: LintRule,
+    /// The alias the issue is about, unless its definition couldn't be parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+}
+
+impl LintIssue {
+    fn new(severity: Severity, rule: LintRule, alias: &Alias, message: String) -> LintIssue {
+        LintIssue {
+            severity,
+            rule,
+            alias: Some(alias.name.to_string()),
+            message,
+            origin: alias.origin.to_owned(),
+        }
+    }
+
+    /// An issue for a line of `alias` output that couldn't be parsed.
+    pub fn from_rejected(rejected: &RejectedLine) -> LintIssue {
+        LintIssue::from_error(
+            &rejected.error,
+            Origin::stdin(rejected.line, &rejected.text),
+        )
+    }
+
+    /// An issue for an rc file line whose alias definition couldn't be read, if it held one.
+    pub fn from_skipped(skipped: &SkippedLine) -> Option<LintIssue> {
+        let origin = Origin {
+            kind: SourceKind::RcFile,
+            file: Some(skipped.file.to_owned()),
+            line: skipped.line,
+            column: 1,
+            plugin: None,
+            text: skipped.text.to_owned(),
+            marked: false,
+        };
+
+        match &skipped.reason {
+            SkipReason::InvalidAlias(error) => Some(LintIssue::from_error(error, origin)),
+            SkipReason::UnterminatedQuote => Some(LintIssue {
+                severity: Severity::Error,
+                rule: LintRule::Quoting,
+                alias: None,
+                message: "a quote is never closed".to_string(),
+                origin: Some(origin),
+            }),
+            _ => None,
+        }
+    }
+
+    fn from_error(error: &AliasError, origin: Origin) -> LintIssue {
+        let (rule, alias) = match error {
+            AliasError::InvalidName(name) | AliasError::IllegalNameChar(name, _) => {
+                (LintRule::InvalidName, Some(name.to_owned()))
+            }
+            AliasError::ParseError(text) if text.contains(['\'', '"']) => (LintRule::Quoting, None),
+            AliasError::ParseError(_) | AliasError::InvalidCommand(_) => (LintRule::Syntax, None),
+        };
+
+        LintIssue {
+            severity: Severity::Error,
+            rule,
+            alias,
+            message: error.to_string(),
+            origin: Some(origin),
+        }
+    }
+}
+
+/// Checks `aliases`, defined for `shell`, for problems; the issues are in the order of the
+/// definitions they're about.
+pub fn lint_aliases(aliases: &[Alias], shell: Shell, options: &FindOptions) -> Vec<LintIssue> {
+    let mut issues = vec![];
+
+    // A shell doesn't expand an alias within itself, so `ls='ls -G'` is a wrapper, not a cycle.
+    let cycles: Vec<Vec<String>> = validation::find_cycles(aliases)
+        .into_iter()
+        .filter(|cycle| cycle.len() > 2)
+        .collect();
+    for cycle in &cycles {
+        if let Some(alias) = aliases.iter().find(|alias| *alias.name.get() == cycle[0]) {
+            issues.push(LintIssue::new(
+                Severity::Error,
+                LintRule::Cycle,
+                alias,
+                format!("expands to itself: {}", cycle.join(" -> ")),
+            ));
+        }
+    }
+    let in_cycle = |alias: &Alias| cycles.iter().flatten().any(|name| name == alias.name.get());
+
+    // The definition of each name that the shell ends up using.
+    let mut active: HashMap<&str, &Alias> = HashMap::new();
+    for alias in aliases {
+        if let Some(previous) = active.insert(alias.name.get(), alias) {
+            let (severity, message) = if previous.command == alias.command {
+                (
+                    Severity::Info,
+                    "defined again with the same command".to_string(),
+                )
+            } else {
+                (
+                    Severity::Warning,
+                    format!("overridden by {}", describe(alias)),
+                )
+            };
+            issues.push(LintIssue::new(
+                severity,
+                LintRule::Overridden,
+                previous,
+                message,
+            ));
+        }
+    }
+
+    let mut commands: HashMap<String, &Alias> = HashMap::new();
+    for alias in aliases
+        .iter()
+        .filter(|alias| std::ptr::eq(active[alias.name.get().as_str()], *alias))
+    {
+        let command = alias
+            .command
+            .get()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if command.is_empty() {
+            issues.push(LintIssue::new(
+                Severity::Warning,
+                LintRule::EmptyBody,
+                alias,
+                "expands to nothing".to_string(),
+            ));
+            continue;
+        }
+
+        if !shell.is_complete(alias.command.get()) {
+            issues.push(LintIssue::new(
+                Severity::Warning,
+                LintRule::Quoting,
+                alias,
+                "its command leaves a quote open".to_string(),
+            ));
+        }
+
+        match commands.get(&command) {
+            Some(first) => issues.push(LintIssue::new(
+                Severity::Info,
+                LintRule::DuplicateCommand,
+                alias,
+                format!("expands to the same command as {}", describe(first)),
+            )),
+            None => {
+                commands.insert(command, alias);
+            }
+        }
+
+        if in_cycle(alias) || is_wrapper(alias) {
+            continue;
+        }
+        let reachable = find_alias_with_options(aliases, alias.command.get(), options)
+            .unwrap_or_else(|err| {
+                debug!("[{}] cannot match {}: {}", function_name!(), alias, err);
+                vec![]
+            })
+            .iter()
+            .any(|matched| matched.name == alias.name);
+        if !reachable {
+            issues.push(LintIssue::new(
+                Severity::Info,
+                LintRule::Unreachable,
+                alias,
+                "is never suggested when its command is typed, as it is ignored or saves too little"
+                    .to_string(),
+            ));
+        }
+    }
+
+    issues.sort_by_key(|issue| {
+        issue
+            .origin
+            .as_ref()
+            .map(|origin| (origin.file.to_owned(), origin.line, origin.column))
+    });
+    debug_value!(issues);
+    issues
+}
+
+/// Finds the aliases named like something `names` knows of, an executable or a builtin, other
+/// than wrappers that call what they shadow, such as `ls='ls -G'` or `cd='builtin cd'`.
+pub fn lint_shadowing(aliases: &[Alias], names: &NameGenerator) -> Vec<LintIssue> {
+    let mut seen = HashSet::new();
+    let mut issues = vec![];
+
+    for alias in aliases.iter().rev() {
+        let name = alias.name.get();
+        if !seen.insert(name) || is_wrapper(alias) {
+            continue;
+        }
+
+        if let Some(collision) = names.collision(name) {
+            issues.push(LintIssue::new(
+                Severity::Warning,
+                LintRule::Shadowing,
+                alias,
+                format!("shadows {}", collision),
+            ));
+        }
+    }
+
+    issues.reverse();
+    debug_value!(issues);
+    issues
+}
+
+/// Finds the aliases whose command, once the aliases it starts with are expanded, `commands`
+/// can't find.
+pub fn lint_broken(aliases: &[Alias], commands: &CommandLookup) -> Vec<LintIssue> {
+    let mut seen = HashSet::new();
+    let mut issues = vec![];
+
+    for alias in aliases.iter().rev() {
+        if !seen.insert(alias.name.get()) {
+            continue;
+        }
+
+        if let Some(missing) = commands.missing_command(alias, aliases) {
+            issues.push(LintIssue::new(
+                Severity::Warning,
+                LintRule::Broken,
+                alias,
+                format!("runs {:?}, which is not installed", missing),
+            ));
+        }
+    }
+
+    issues.reverse();
+    debug_value!(issues);
+    issues
+}
+
+/// Whether the alias runs the command it's named after, directly or through `command`,
+/// `builtin` or a backslash.
+fn is_wrapper(alias: &Alias) -> bool {
+    let name = alias.name.get();
+    let words = split_words(alias.command.get());
+    let mut words = words
+        .iter()
+        .skip_while(|word| matches!(word.as_str(), "command" | "builtin" | "exec"));
+
+    words.next().is_some_and(|word| {
+        let word = word.strip_prefix('\\').unwrap_or(word);
+        word == name
+            || Path::new(word)
+                .file_name()
+                .is_some_and(|file| file == name.as_str())
+    })
+}
+
+/// The alias with where it was defined, if known.
+fn describe(alias: &Alias) -> String {
+    match &alias.origin {
+        Some(origin) => format!("{} at {}", alias.name, origin.location(None)),
+        None => alias.name.to_string(),
+    }
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(origin) = &self.origin {
+            write!(f, "{}: ", origin.location(None))?;
+        }
+        write!(f, "{}: ", self.severity)?;
+        if let Some(alias) = &self.alias {
+            write!(f, "{} ", alias)?;
+        }
+        write!(f, "{} [{}]", self.message, self.rule)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintRule::Cycle => write!(f, "cycle"),
+            LintRule::EmptyBody => write!(f, "empty-body"),
+            LintRule::InvalidName => write!(f, "invalid-name"),
+            LintRule::Overridden => write!(f, "overridden"),
+            LintRule::DuplicateCommand => write!(f, "duplicate-command"),
+            LintRule::Unreachable => write!(f, "unreachable"),
+            LintRule::Shadowing => write!(f, "shadowing"),
+            LintRule::Broken => write!(f, "broken"),
+            LintRule::Quoting => write!(f, "quoting"),
+            LintRule::Syntax => write!(f, "syntax"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint_aliases, lint_shadowing, LintIssue, LintRule, Severity};
+    use crate::{read_aliases, Alias, FindOptions, NameGenerator, Shell};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_reports_issues_with_their_location() {
+        let input = read_aliases(
+            "gco='git checkout'\n\
+             c='d'\n\
+             d='c'\n\
+             e=''\n\
+             gco='git checkout --'\n\
+             co='git checkout --'\n\
+             l='ls'\n\
+             q='echo \"hi'\n\
+             'bad name'='ls'\n"
+                .as_bytes(),
+            Shell::Bash,
+        );
+
+        let options = FindOptions {
+            min_savings: 2,
+            ..FindOptions::default()
+        };
+        let mut issues = lint_aliases(&input.aliases, Shell::Bash, &options);
+        issues.extend(input.rejected.iter().map(LintIssue::from_rejected));
+        let summary: Vec<(usize, Severity, LintRule)> = issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue.origin.as_ref().unwrap().line,
+                    issue.severity,
+                    issue.rule,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (1, Severity::Warning, LintRule::Overridden),
+                (2, Severity::Error, LintRule::Cycle),
+                (4, Severity::Warning, LintRule::EmptyBody),
+                (6, Severity::Info, LintRule::DuplicateCommand),
+                (7, Severity::Info, LintRule::Unreachable),
+                (8, Severity::Warning, LintRule::Quoting),
+                (9, Severity::Error, LintRule::InvalidName),
+            ]
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "stdin:2:1: error: c expands to itself: c -> d -> c [cycle]"
+        );
+    }
+
+    #[test]
+    fn it_reports_aliases_that_shadow_executables_and_builtins() {
+        let temp = TempDir::new().unwrap();
+        let bin = temp.path();
+        for executable in ["gs", "ls"] {
+            let path = bin.join(executable);
+            fs::write(&path, "#!/bin/sh\n").unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+        }
+
+        let aliases: Vec<Alias> = [
+            "gs='git status'",
+            "ls='ls -G'",
+            "cd='z'",
+            "pushd='builtin pushd -q'",
+            "r='\\r'",
+            "gst='git status'",
+        ]
+        .into_iter()
+        .map(|line| Alias::from(line).unwrap())
+        .collect();
+        let names = NameGenerator::new(Shell::Zsh).with_path(&bin.display().to_string());
+
+        let shadowing: Vec<(String, String)> = lint_shadowing(&aliases, &names)
+            .into_iter()
+            .map(|issue| (issue.alias.unwrap(), issue.message))
+            .collect();
+        assert_eq!(
+            shadowing,
+            vec![
+                (
+                    "gs".to_string(),
+                    format!("shadows {}", bin.join("gs").display())
+                ),
+                ("cd".to_string(), "shadows a zsh builtin".to_string()),
+            ]
+        );
+    }
+}
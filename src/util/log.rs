@@ -7,7 +7,10 @@ use fancy_regex::Regex;
 #[allow(unused_imports)]
 pub use log::{debug, error, info, trace, warn};
 use log::{Level, LevelFilter, Record};
-use std::io::{Error, Write};
+use std::{
+    io::{Error, Write},
+    path::Path,
+};
 
 // Workaround to use prinltn! for logs in test mode.
 #[cfg(test)]
@@ -16,7 +19,7 @@ pub use std::{
     println as trace, println as debug, println as info, println as warn, println as error,
 };
 
-use crate::{AliasError, Command};
+use crate::{AliasError, Command, ConfigError, HistoryError, Shell};
 
 trait CustomLog: CologStyle {
     fn suffix_message(&self, level: &Level) -> String;
@@ -111,6 +114,14 @@ pub enum ErrorCode<'a> {
     InvalidCommand(String),
     InvalidAlias(String),
     NoOutput,
+    InvalidConfig(&'a ConfigError),
+    StrictMatch,
+    StrictUnsupported(Shell),
+    UnknownAlias(&'a str),
+    ParseErrors(usize),
+    NoHistoryInput,
+    UnreadableHistory(&'a Path, HistoryError),
+    LintErrors(usize),
 }
 
 impl<'a> ErrorCode<'a> {
@@ -122,7 +133,7 @@ impl<'a> ErrorCode<'a> {
         format!("Error {}: An unexpected error occured", self.discriminant())
     }
 
-    pub fn log_debug(&self, function_name: &str) -> () {
+    pub fn log_debug(&self, function_name: &str) {
         match self {
             ErrorCode::RegexParse(regex, error) => {
                 debug!(
@@ -164,10 +175,40 @@ impl<'a> ErrorCode<'a> {
             ErrorCode::NoOutput => {
                 debug!("[{function_name}] Couldn't find any matching aliases");
             }
+            ErrorCode::InvalidConfig(error) => {
+                debug!(
+                    "[{function_name}] Could not load the configuration: {:?}",
+                    error
+                );
+            }
+            ErrorCode::StrictMatch => {
+                debug!("[{function_name}] Blocking a command that has a matching alias");
+            }
+            ErrorCode::StrictUnsupported(shell) => {
+                debug!("[{function_name}] Strict mode was requested for {shell}");
+            }
+            ErrorCode::UnknownAlias(name) => {
+                debug!("[{function_name}] No alias is named {:?}", name);
+            }
+            ErrorCode::ParseErrors(count) => {
+                debug!("[{function_name}] {count} alias lines could not be parsed");
+            }
+            ErrorCode::NoHistoryInput => {
+                debug!("[{function_name}] No history file was given or found");
+            }
+            ErrorCode::UnreadableHistory(path, error) => {
+                debug!(
+                    "[{function_name}] Could not read history {:?}: {:?}",
+                    path, error
+                );
+            }
+            ErrorCode::LintErrors(count) => {
+                debug!("[{function_name}] Lint found {count} errors");
+            }
         };
     }
 
-    pub fn log_err(&self) -> () {
+    pub fn log_err(&self) {
         match self {
             ErrorCode::NoCommandInput => {
                 error!("No command provided. Please specify a command to execute. For help, use the '-h' or '--help' flag.");
@@ -187,6 +228,33 @@ impl<'a> ErrorCode<'a> {
             ErrorCode::NoOutput => {
                 error!("There is no alias matching your command.");
             }
+            ErrorCode::InvalidConfig(error) => {
+                error!("Could not load the configuration: {}.", error);
+            }
+            ErrorCode::StrictUnsupported(shell) => {
+                error!("Strict mode is not supported for {shell}, since it cannot cancel commands from its hooks.");
+            }
+            ErrorCode::UnknownAlias(name) => {
+                error!("There is no alias named {:?}.", name);
+            }
+            ErrorCode::ParseErrors(count) => {
+                error!(
+                    "{count} alias lines could not be parsed, and --deny-parse-errors was passed."
+                );
+            }
+            ErrorCode::NoHistoryInput => {
+                error!("No history file found. Please pass one with '--history <PATH>'.");
+            }
+            ErrorCode::UnreadableHistory(path, error) => {
+                error!(
+                    "Could not read the history file {}: {}.",
+                    path.display(),
+                    error
+                );
+            }
+            ErrorCode::LintErrors(count) => {
+                error!("Found {count} errors in the alias definitions.");
+            }
             _ => error!("{}", self.default_err()),
         }
     }
@@ -198,9 +266,9 @@ impl<'a> ErrorCode<'a> {
     }
 }
 
-impl<'a> Into<i32> for ErrorCode<'a> {
-    fn into(self) -> i32 {
-        self.discriminant() as i32
+impl<'a> From<ErrorCode<'a>> for i32 {
+    fn from(val: ErrorCode<'a>) -> Self {
+        val.discriminant() as i32
     }
 }
 
@@ -208,7 +276,9 @@ impl<'a> From<AliasError> for ErrorCode<'a> {
     fn from(value: AliasError) -> Self {
         match value {
             AliasError::InvalidCommand(command) => ErrorCode::InvalidCommand(command),
-            AliasError::InvalidName(name) => ErrorCode::InvalidName(name),
+            AliasError::InvalidName(name) | AliasError::IllegalNameChar(name, _) => {
+                ErrorCode::InvalidName(name)
+            }
             AliasError::ParseError(alias) => ErrorCode::InvalidAlias(alias),
         }
     }
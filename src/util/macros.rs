@@ -59,6 +59,7 @@ macro_rules! unwrap_or_panic {
 /// let panics = unwrap_or_panic_err!(foo, ErrorCode::Foo, "some data"); // Passes the std::io::Error as the 2nd argument; calls ErrorCode::Foo.log, and panics with the default error message
 /// ```
 ///
+#[allow(unused_macros)]
 macro_rules! unwrap_or_panic_err {
     ($result:expr, $error_variant:path $(, $arg:expr)* ) => {{
         $result.unwrap_or_else(|err| $error_variant($($arg),*, err).log_and_panic(function_name!()))
@@ -77,4 +78,5 @@ pub(crate) use debug_value;
 pub(crate) use function_name;
 #[allow(unused_imports)]
 pub(crate) use unwrap_or_panic;
+#[allow(unused_imports)]
 pub(crate) use unwrap_or_panic_err;
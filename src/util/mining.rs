@@ -0,0 +1,160 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use super::naming::NameGenerator;
+use crate::{find_alias_with_options, FindOptions, MatchStrategy};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A command prefix typed often enough to deserve an alias.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AliasCandidate {
+    pub alias: Alias,
+    /// How many history entries start with the command.
+    pub count: usize,
+    /// The characters saved each time the alias is typed instead of the command.
+    pub savings: usize,
+    /// `count * savings`, which candidates are ranked by.
+    pub score: usize,
+}
+
+/// Finds the word prefixes of `history` typed at least `min_count` times that no alias in
+/// `aliases` covers, and proposes an alias for each, best first, named by `names`.
+///
+/// A prefix is left out when a longer prefix was typed just as often, since aliasing the
+/// longer one saves more.
+pub fn mine_aliases(
+    history: &[String],
+    aliases: &[Alias],
+    options: &FindOptions,
+    names: &mut NameGenerator,
+    min_count: usize,
+) -> Vec<AliasCandidate> {
+    let mut counts: HashMap<Vec<String>, usize> = HashMap::new();
+
+    for command in history {
+        // The lines after the first of a multi-line command are separate commands.
+        let first_line = command.trim().lines().next().unwrap_or_default();
+        let words: Vec<String> = split_words(first_line)
+            .into_iter()
+            .take_while(|word| !is_operator(word))
+            .collect();
+
+        for length in 1..=words.len() {
+            *counts.entry(words[..length].to_vec()).or_default() += 1;
+        }
+    }
+
+    let mut longest_extension: HashMap<&[String], usize> = HashMap::new();
+    for (prefix, &count) in &counts {
+        if let Some((_, parent)) = prefix.split_last() {
+            let extension = longest_extension.entry(parent).or_default();
+            *extension = (*extension).max(count);
+        }
+    }
+
+    let exact = FindOptions {
+        match_strategy: MatchStrategy::Exact,
+        ..options.to_owned()
+    };
+    let mut frequent: Vec<(String, usize)> = counts
+        .iter()
+        .filter(|(prefix, &count)| {
+            count >= min_count && longest_extension.get(prefix.as_slice()) < Some(&count)
+        })
+        .map(|(prefix, &count)| (prefix.join(" "), count))
+        .filter(|(command, _)| !is_covered(aliases, command, &exact))
+        .collect();
+    frequent.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut candidates = vec![];
+
+    for (command, count) in frequent {
+        let Some(name) = names
+            .suggest(&command)
+            .into_iter()
+            .next()
+            .and_then(|name| Name::for_shell(&name, names.shell()).ok())
+        else {
+            debug!("[{}] no name for {:?}", function_name!(), command);
+            continue;
+        };
+        let savings = command.len().saturating_sub(name.get().len());
+
+        if savings == 0 || savings < options.min_savings {
+            continue;
+        }
+
+        let Ok(command) = Command::new(&command) else {
+            continue;
+        };
+        names.reserve(name.get());
+        candidates.push(AliasCandidate {
+            alias: Alias::new(name, command),
+            count,
+            savings,
+            score: count * savings,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then(b.count.cmp(&a.count)));
+    debug_value!(candidates);
+    candidates
+}
+
+/// Whether an existing alias already expands to exactly `command`, or the command is ignored.
+fn is_covered(aliases: &[Alias], command: &str, options: &FindOptions) -> bool {
+    options.ignore.ignores_command(command)
+        || find_alias_with_options(aliases, command, options)
+            .map(|matches| !matches.is_empty())
+            .unwrap_or(true)
+}
+
+/// Words that end the command, so prefixes never span a pipe, a list or a redirection.
+fn is_operator(word: &str) -> bool {
+    word.starts_with(['|', '&', ';', '<', '>']) || word.ends_with(';')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mine_aliases;
+    use crate::{Alias, FindOptions, NameGenerator, Shell};
+
+    #[test]
+    fn it_proposes_aliases_for_frequent_prefixes() {
+        let history: Vec<String> = [
+            "git commit -m 'first'",
+            "git commit -m 'second'",
+            "git commit -m 'third' && git push",
+            "kubectl get pods -n production",
+            "kubectl get pods -n production",
+            "kubectl get pods -n production | grep api",
+            "git status",
+            "git status",
+            "git status",
+            "ls",
+            "ls",
+            "ls",
+        ]
+        .map(str::to_string)
+        .to_vec();
+        let aliases = vec![Alias::from("gst='git status'").unwrap()];
+
+        let mut names = NameGenerator::new(Shell::Zsh).with_aliases(&aliases);
+        let candidates = mine_aliases(&history, &aliases, &FindOptions::default(), &mut names, 3);
+        let proposed: Vec<(String, usize)> = candidates
+            .iter()
+            .map(|candidate| (candidate.alias.to_string(), candidate.count))
+            .collect();
+
+        assert_eq!(
+            proposed,
+            vec![
+                ("kgpnp='kubectl get pods -n production'".to_string(), 3),
+                ("gcm='git commit -m'".to_string(), 3),
+                ("g='git'".to_string(), 6),
+                ("l='ls'".to_string(), 3),
+            ]
+        );
+    }
+}
@@ -1,4 +1,25 @@
-pub mod validation;
-pub mod macros;
+pub mod alias;
+pub mod audit;
+pub mod broken;
+pub mod config;
+pub mod env_var;
+pub mod function;
+pub mod history;
+pub mod ignore;
+pub mod input;
+pub mod lint;
 pub mod log;
-pub mod alias;
\ No newline at end of file
+pub mod macros;
+pub mod mining;
+pub mod named_dir;
+pub mod naming;
+pub mod normalize;
+pub mod pattern;
+pub mod prune;
+pub mod rcfile;
+pub mod shell;
+pub mod stats;
+pub mod strict;
+pub mod suggestion;
+pub mod throttle;
+pub mod validation;
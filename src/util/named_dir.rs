@@ -0,0 +1,137 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use super::normalize::PathNormalizer;
+use super::suggestion::*;
+use std::path::{Path, PathBuf};
+
+/// A zsh named directory, defined with `hash -d name=path` and typed as `~name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedDirectory {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Reads the `name=path` lines printed by `hash -d`, skipping the ones that aren't absolute paths.
+pub fn parse_named_directories(output: &str) -> Vec<NamedDirectory> {
+    let directories = output
+        .lines()
+        .filter_map(|line| {
+            let (name, path) = line.split_once('=')?;
+            let name = unquote(name.trim()).ok()?;
+            let path = PathBuf::from(unquote(path.trim()).ok()?);
+
+            if name.is_empty() || !path.is_absolute() {
+                debug!("[{}] skipping {:?}", function_name!(), line);
+                return None;
+            }
+
+            Some(NamedDirectory { name, path })
+        })
+        .collect();
+
+    debug_value!(directories);
+    directories
+}
+
+/// Suggests writing the arguments of `needle` that are inside a named directory as `~name/...`.
+pub fn find_named_directories(
+    directories: &[NamedDirectory],
+    needle: &str,
+    paths: &PathNormalizer,
+) -> Vec<Suggestion> {
+    let mut names = vec![];
+    let words: Vec<String> = split_words(needle)
+        .into_iter()
+        .enumerate()
+        .map(|(index, word)| {
+            if index == 0 || (word.starts_with(['-', '~']) && !word.starts_with("~/")) {
+                return word;
+            }
+
+            let unquoted = unquote(&word).unwrap_or_else(|_| word.to_owned());
+            let normalized = PathBuf::from(paths.normalize_word(&unquoted));
+
+            match shorten(directories, &normalized) {
+                Some((name, shortened)) if shortened.len() < word.len() => {
+                    names.push(format!("~{}", name));
+                    shortened
+                }
+                _ => word,
+            }
+        })
+        .collect();
+
+    if names.is_empty() {
+        return vec![];
+    }
+
+    names.dedup();
+    vec![Suggestion {
+        kind: SuggestionKind::NamedDirectory,
+        shortcut: names.join(", "),
+        rewritten: words.join(" "),
+    }]
+}
+
+/// The `~name/rest` form of `path`, using the directory that covers the most of it.
+fn shorten<'a>(directories: &'a [NamedDirectory], path: &Path) -> Option<(&'a str, String)> {
+    let directory = directories
+        .iter()
+        .filter(|directory| path.starts_with(&directory.path))
+        .max_by_key(|directory| directory.path.components().count())?;
+
+    let rest = path.strip_prefix(&directory.path).ok()?;
+    let shortened = if rest.as_os_str().is_empty() {
+        format!("~{}", directory.name)
+    } else {
+        format!("~{}/{}", directory.name, rest.display())
+    };
+
+    // Words with spaces or other special characters would need quoting, which `~name` doesn't survive.
+    if shortened.contains(|c: char| c.is_whitespace() || "'\"$`\\".contains(c)) {
+        return None;
+    }
+
+    Some((&directory.name, shortened))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_named_directories, parse_named_directories};
+    use crate::PathNormalizer;
+    use std::path::Path;
+
+    #[test]
+    fn it_parses_hash_output() {
+        let directories =
+            parse_named_directories("w=/home/me/work\n'dl'='/home/me/dl'\nbad=relative\n");
+
+        let names: Vec<&str> = directories
+            .iter()
+            .map(|directory| directory.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["w", "dl"]);
+    }
+
+    #[test]
+    fn it_suggests_named_directories() {
+        let directories = parse_named_directories("w=/home/me/work\nap=/home/me/work/api\n");
+        let paths = PathNormalizer::default().with_home(Path::new("/home/me"));
+        let rewritten = |needle: &str| -> Vec<String> {
+            find_named_directories(&directories, needle, &paths)
+                .into_iter()
+                .map(|suggestion| suggestion.rewritten)
+                .collect()
+        };
+
+        assert_eq!(rewritten("cd /home/me/work"), vec!["cd ~w"]);
+        assert_eq!(
+            rewritten("cp ~/work/notes.md /home/me/work/api/docs/"),
+            vec!["cp ~w/notes.md ~ap/docs"]
+        );
+        assert!(rewritten("cd ~w/src").is_empty());
+        assert!(rewritten("cd /home/me/workshop").is_empty());
+        assert!(rewritten("ls '/home/me/work/my notes'").is_empty());
+    }
+}
@@ -0,0 +1,558 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use super::shell::Shell;
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Abbreviations in the style of the Oh-My-Zsh `git` and `kubectl` plugins, for command words.
+const COMMAND_ABBREVIATIONS: [(&str, &str); 7] = [
+    ("git", "g"),
+    ("kubectl", "k"),
+    ("docker", "d"),
+    ("docker-compose", "dco"),
+    ("terraform", "tf"),
+    ("systemctl", "sc"),
+    ("helm", "h"),
+];
+
+/// Abbreviations in the style of the Oh-My-Zsh `git` plugin, for subcommands.
+const SUBCOMMAND_ABBREVIATIONS: [(&str, &str); 26] = [
+    ("add", "a"),
+    ("bisect", "bs"),
+    ("blame", "bl"),
+    ("branch", "b"),
+    ("checkout", "co"),
+    ("cherry-pick", "cp"),
+    ("clone", "cl"),
+    ("commit", "c"),
+    ("describe", "desc"),
+    ("diff", "d"),
+    ("fetch", "f"),
+    ("log", "lg"),
+    ("merge", "m"),
+    ("pull", "l"),
+    ("push", "p"),
+    ("rebase", "rb"),
+    ("remote", "r"),
+    ("reset", "rs"),
+    ("restore", "rst"),
+    ("revert", "rev"),
+    ("show", "sh"),
+    ("stash", "sta"),
+    ("status", "st"),
+    ("switch", "sw"),
+    ("tag", "t"),
+    ("worktree", "wt"),
+];
+
+/// What `compgen -b` lists in bash.
+const BASH_BUILTINS: [&str; 61] = [
+    ".",
+    ":",
+    "[",
+    "alias",
+    "bg",
+    "bind",
+    "break",
+    "builtin",
+    "caller",
+    "cd",
+    "command",
+    "compgen",
+    "complete",
+    "compopt",
+    "continue",
+    "declare",
+    "dirs",
+    "disown",
+    "echo",
+    "enable",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "false",
+    "fc",
+    "fg",
+    "getopts",
+    "hash",
+    "help",
+    "history",
+    "jobs",
+    "kill",
+    "let",
+    "local",
+    "logout",
+    "mapfile",
+    "popd",
+    "printf",
+    "pushd",
+    "pwd",
+    "read",
+    "readarray",
+    "readonly",
+    "return",
+    "set",
+    "shift",
+    "shopt",
+    "source",
+    "suspend",
+    "test",
+    "times",
+    "trap",
+    "true",
+    "type",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unset",
+    "wait",
+];
+
+/// The builtins of zsh and of the modules it loads by default, plus `compdef` from `compinit`.
+const ZSH_BUILTINS: [&str; 103] = [
+    "-",
+    ".",
+    ":",
+    "[",
+    "alias",
+    "autoload",
+    "bg",
+    "bindkey",
+    "break",
+    "builtin",
+    "bye",
+    "cd",
+    "chdir",
+    "command",
+    "compadd",
+    "comparguments",
+    "compcall",
+    "compctl",
+    "compdef",
+    "compdescribe",
+    "compfiles",
+    "compgroups",
+    "compquote",
+    "compset",
+    "comptags",
+    "comptry",
+    "compvalues",
+    "continue",
+    "declare",
+    "dirs",
+    "disable",
+    "disown",
+    "echo",
+    "echotc",
+    "echoti",
+    "emulate",
+    "enable",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "false",
+    "fc",
+    "fg",
+    "float",
+    "functions",
+    "getln",
+    "getopts",
+    "hash",
+    "history",
+    "integer",
+    "jobs",
+    "kill",
+    "let",
+    "limit",
+    "local",
+    "log",
+    "logout",
+    "noglob",
+    "popd",
+    "print",
+    "printf",
+    "pushd",
+    "pushln",
+    "pwd",
+    "r",
+    "read",
+    "readonly",
+    "rehash",
+    "return",
+    "sched",
+    "set",
+    "setopt",
+    "shift",
+    "source",
+    "suspend",
+    "test",
+    "times",
+    "trap",
+    "true",
+    "ttyctl",
+    "type",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unfunction",
+    "unhash",
+    "unlimit",
+    "unset",
+    "unsetopt",
+    "vared",
+    "wait",
+    "whence",
+    "where",
+    "which",
+    "zcompile",
+    "zformat",
+    "zle",
+    "zmodload",
+    "zparseopts",
+    "zregexparse",
+    "zstyle",
+];
+
+/// What `builtin -n` lists in fish.
+const FISH_BUILTINS: [&str; 61] = [
+    ".",
+    ":",
+    "[",
+    "_",
+    "abbr",
+    "and",
+    "argparse",
+    "begin",
+    "bg",
+    "bind",
+    "block",
+    "break",
+    "breakpoint",
+    "builtin",
+    "case",
+    "cd",
+    "command",
+    "commandline",
+    "complete",
+    "contains",
+    "continue",
+    "count",
+    "disown",
+    "echo",
+    "else",
+    "emit",
+    "end",
+    "eval",
+    "exec",
+    "exit",
+    "false",
+    "fg",
+    "for",
+    "function",
+    "functions",
+    "history",
+    "if",
+    "jobs",
+    "math",
+    "not",
+    "or",
+    "path",
+    "printf",
+    "pwd",
+    "random",
+    "read",
+    "realpath",
+    "return",
+    "set",
+    "set_color",
+    "source",
+    "status",
+    "string",
+    "switch",
+    "test",
+    "time",
+    "true",
+    "type",
+    "ulimit",
+    "wait",
+    "while",
+];
+
+const HOME_ROW: &str = "asdfghjkl";
+
+/// Proposes short names for a command, leaving out the ones already taken by an alias,
+/// an executable on `$PATH` or a builtin of the shell.
+#[derive(Debug, Clone)]
+pub struct NameGenerator {
+    shell: Shell,
+    taken: HashSet<String>,
+    path: Vec<PathBuf>,
+}
+
+impl NameGenerator {
+    /// A generator that only knows the shell's builtins.
+    pub fn new(shell: Shell) -> NameGenerator {
+        NameGenerator {
+            shell,
+            taken: HashSet::new(),
+            path: vec![],
+        }
+    }
+
+    pub fn shell(&self) -> Shell {
+        self.shell
+    }
+
+    pub fn with_aliases(mut self, aliases: &[Alias]) -> NameGenerator {
+        self.taken
+            .extend(aliases.iter().map(|alias| alias.name.get().to_owned()));
+        self
+    }
+
+    /// Also refuses the executables found in `path`, a list of directories like `$PATH`.
+    pub fn with_path(self, path: &str) -> NameGenerator {
+        NameGenerator {
+            path: env::split_paths(path).collect(),
+            ..self
+        }
+    }
+
+    /// Refuses the executables on `$PATH`.
+    pub fn with_env_path(self) -> NameGenerator {
+        let path = env::var("PATH").unwrap_or_default();
+        self.with_path(&path)
+    }
+
+    /// Marks a name as taken, such as one just given to a new alias.
+    pub fn reserve(&mut self, name: &str) {
+        self.taken.insert(name.to_string());
+    }
+
+    /// Why `name` can't be used, if it collides with something.
+    pub fn collision(&self, name: &str) -> Option<String> {
+        if self.taken.contains(name) {
+            return Some("an alias".to_string());
+        }
+
+        if is_builtin(self.shell, name) {
+            return Some(format!("a {} builtin", self.shell));
+        }
+
+        find_executable(&self.path, name).map(|path| format!("{}", path.display()))
+    }
+
+    /// Names for `command`, shortest and easiest to type first. Initials (`gri` for
+    /// `git rebase --interactive`), Oh-My-Zsh abbreviations (`grbi`) and longer prefixes of the
+    /// subcommand (`grebi`) are tried, and names that collide are left out.
+    pub fn suggest(&self, command: &str) -> Vec<String> {
+        let words: Vec<String> = split_words(command)
+            .iter()
+            .map(|word| unquote(word).unwrap_or_else(|_| word.to_owned()))
+            .collect();
+        let Some((first, rest)) = words.split_first() else {
+            return vec![];
+        };
+
+        let command_word = Path::new(first)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| first.to_owned());
+        let head = abbreviation(&COMMAND_ABBREVIATIONS, &command_word)
+            .unwrap_or_else(|| initials(&command_word));
+        let tails: Vec<String> = rest.iter().map(|word| initials(word)).collect();
+
+        let mut names = vec![[initials(&command_word)]
+            .into_iter()
+            .chain(tails.iter().cloned())
+            .collect::<String>()];
+        let mut omz = head.to_owned();
+        let mut prefixed = vec![head.to_owned(), head.to_owned()];
+
+        for (index, word) in rest.iter().enumerate() {
+            let short = abbreviation(&SUBCOMMAND_ABBREVIATIONS, word);
+            omz += short.as_deref().unwrap_or(&tails[index]);
+
+            for (length, name) in [2, 3].into_iter().zip(prefixed.iter_mut()) {
+                match index {
+                    0 if is_identifier(word) => name.extend(word.chars().take(length)),
+                    _ => name.push_str(&tails[index]),
+                }
+            }
+        }
+        names.push(omz);
+        names.extend(prefixed);
+
+        let mut seen = HashSet::new();
+        let mut names: Vec<String> = names
+            .into_iter()
+            .map(|name| name.to_lowercase())
+            .filter(|name| !name.is_empty() && name.len() < command.len())
+            .filter(|name| seen.insert(name.to_owned()))
+            .collect();
+        names.sort_by_key(|name| (name.len(), typing_cost(name)));
+
+        names.retain(|name| match self.collision(name) {
+            Some(collision) => {
+                debug!(
+                    "[{}] {:?} is taken by {}",
+                    function_name!(),
+                    name,
+                    collision
+                );
+                false
+            }
+            None => Name::for_shell(name, self.shell).is_ok(),
+        });
+
+        debug_value!(command, names);
+        names
+    }
+}
+
+/// How hard a name is to type: every key costs 2, plus 1 off the home row and 2 more for shifted keys.
+pub fn typing_cost(name: &str) -> usize {
+    name.chars()
+        .map(|c| {
+            let shifted = c.is_uppercase() || !(c.is_alphanumeric() || "-_.,/;'".contains(c));
+            let home = HOME_ROW.contains(c.to_ascii_lowercase());
+            2 + usize::from(!home) + 2 * usize::from(shifted)
+        })
+        .sum()
+}
+
+/// The first letter of a word, the letters of short flags like `-am`, and the first letter of
+/// each part of long flags like `--force-with-lease`.
+fn initials(word: &str) -> String {
+    if let Some(flag) = word.strip_prefix("--") {
+        return flag
+            .split('=')
+            .next()
+            .unwrap_or_default()
+            .split('-')
+            .filter_map(|part| part.chars().find(|c| c.is_alphanumeric()))
+            .collect();
+    }
+
+    if let Some(flags) = word.strip_prefix('-') {
+        return flags.chars().filter(|c| c.is_alphanumeric()).collect();
+    }
+
+    word.chars()
+        .find(|c| c.is_alphanumeric())
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// Whether `name` is a builtin of `shell`.
+pub(crate) fn is_builtin(shell: Shell, name: &str) -> bool {
+    let builtins: &[&str] = match shell {
+        Shell::Bash => &BASH_BUILTINS,
+        Shell::Zsh => &ZSH_BUILTINS,
+        Shell::Fish => &FISH_BUILTINS,
+        // Nushell's commands are too many to list, and aliases are only read from the others.
+        Shell::Nu => &[],
+    };
+    builtins.contains(&name)
+}
+
+/// The first executable named `name` in the directories of `path`.
+pub(crate) fn find_executable(path: &[PathBuf], name: &str) -> Option<PathBuf> {
+    path.iter()
+        .map(|dir| dir.join(name))
+        .find(|path| is_executable(path))
+}
+
+fn abbreviation(table: &[(&str, &str)], word: &str) -> Option<String> {
+    table
+        .iter()
+        .find(|(full, _)| *full == word)
+        .map(|(_, short)| short.to_string())
+}
+
+fn is_identifier(word: &str) -> bool {
+    word.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+pub(crate) fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{typing_cost, NameGenerator};
+    use crate::{Alias, Shell};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_suggests_mnemonic_names() {
+        let names = NameGenerator::new(Shell::Zsh);
+
+        assert_eq!(
+            names.suggest("git rebase --interactive"),
+            vec!["gri", "grbi", "grei", "grebi"]
+        );
+        assert_eq!(
+            names.suggest("git push --force-with-lease"),
+            vec!["gpfwl", "gpufwl", "gpusfwl"]
+        );
+        assert_eq!(
+            names.suggest("kubectl get pods"),
+            vec!["kgp", "kgep", "kgetp"]
+        );
+        assert_eq!(
+            names.suggest("git commit -am"),
+            vec!["gcam", "gcoam", "gcomam"]
+        );
+        assert_eq!(names.suggest("ls"), vec!["l"]);
+    }
+
+    #[test]
+    fn it_refuses_names_that_collide() {
+        let temp = TempDir::new().unwrap();
+        let bin = temp.path();
+        let gri = bin.join("gri");
+        fs::write(&gri, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&gri, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let names = NameGenerator::new(Shell::Zsh)
+            .with_aliases(&[Alias::from("grbi='git rebase -i'").unwrap()])
+            .with_path(&bin.display().to_string());
+        assert_eq!(
+            names.suggest("git rebase --interactive"),
+            vec!["grei", "grebi"]
+        );
+        assert!(names.collision("gri").is_some());
+        assert!(names.collision("r").is_some());
+        assert!(NameGenerator::new(Shell::Bash).collision("r").is_none());
+        assert!(NameGenerator::new(Shell::Bash).collision("shopt").is_some());
+        assert!(NameGenerator::new(Shell::Bash).collision("wait").is_some());
+        assert!(NameGenerator::new(Shell::Fish).collision("shopt").is_none());
+        assert!(NameGenerator::new(Shell::Fish)
+            .collision("declare")
+            .is_none());
+
+        assert!(typing_cost("gs") < typing_cost("gQ"));
+    }
+}
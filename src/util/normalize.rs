@@ -0,0 +1,248 @@
+use super::alias::*;
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+};
+
+/// Rewrites path-like words to a canonical form, so `~/work/`, `$HOME/work` and `/home/me/work`
+/// compare equal. Other variables are left alone, since their values when the command runs may
+/// differ from alias-helper's environment.
+#[derive(Debug, Clone, Default)]
+pub struct PathNormalizer {
+    home: Option<PathBuf>,
+    /// When set, relative paths are resolved against it.
+    cwd: Option<PathBuf>,
+}
+
+impl PathNormalizer {
+    /// Uses `$HOME` from the environment, and leaves relative paths alone.
+    pub fn new() -> PathNormalizer {
+        PathNormalizer {
+            home: env::var_os("HOME").map(PathBuf::from),
+            cwd: None,
+        }
+    }
+
+    pub fn with_home(self, home: &Path) -> PathNormalizer {
+        PathNormalizer {
+            home: Some(home.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn with_cwd(self, cwd: &Path) -> PathNormalizer {
+        PathNormalizer {
+            cwd: Some(cwd.to_owned()),
+            ..self
+        }
+    }
+
+    /// Normalizes the words of the command that look like paths, as well as the directory given
+    /// to `cd` and `pushd`. Words are quoted the same way however they were typed, so `'main'`
+    /// equals `main` but `"a b"` stays one word.
+    pub fn normalize_command(&self, command: &str) -> String {
+        let words: Vec<(String, String)> = split_words(command)
+            .into_iter()
+            .map(|raw| (unquote(&raw).unwrap_or_else(|_| raw.to_owned()), raw))
+            .collect();
+        let takes_directory = matches!(
+            words.first().map(|(word, _)| word.as_str()),
+            Some("cd" | "pushd")
+        );
+
+        words
+            .iter()
+            .enumerate()
+            .map(|(index, (word, raw))| {
+                let word = match (index, &self.cwd) {
+                    _ if !expands(raw) => word.to_owned(),
+                    (1, Some(cwd))
+                        if takes_directory && !word.starts_with(['-', '/', '~', '$']) =>
+                    {
+                        clean(&cwd.join(word)).display().to_string()
+                    }
+                    _ => self.normalize_word(word),
+                };
+                requote(&word)
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Expands `~` and `$HOME`, drops trailing slashes, and resolves relative paths
+    /// against `cwd` when it is set. Other words are returned unchanged.
+    pub fn normalize_word(&self, word: &str) -> String {
+        if word.starts_with('-') || word.contains("://") {
+            return word.to_string();
+        }
+
+        let expanded = match self.expand_prefix(word) {
+            Some(expanded) => expanded,
+            None if is_relative_path(word) => match &self.cwd {
+                Some(cwd) => cwd.join(word),
+                None => return trim_trailing_slashes(word).to_string(),
+            },
+            None => return word.to_string(),
+        };
+
+        clean(&expanded).display().to_string()
+    }
+
+    /// Expands a leading `~`, `$HOME` or `${HOME}`.
+    fn expand_prefix(&self, word: &str) -> Option<PathBuf> {
+        if word == "~" || word.starts_with("~/") {
+            return Some(self.home.as_ref()?.join(word[1..].trim_start_matches('/')));
+        }
+
+        if word.starts_with('/') {
+            return Some(PathBuf::from(word));
+        }
+
+        let variable = word.strip_prefix('$')?;
+        let (name, rest) = match variable.strip_prefix('{') {
+            Some(braced) => braced.split_once('}')?,
+            None => variable.split_at(
+                variable
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(variable.len()),
+            ),
+        };
+
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+
+        if name != "HOME" {
+            return None;
+        }
+
+        Some(self.home.as_ref()?.join(rest.trim_start_matches('/')))
+    }
+}
+
+/// Whether the shell expands `~` and `$HOME` in a word as written: not in single quotes, and
+/// `~` not in double quotes either.
+fn expands(raw: &str) -> bool {
+    !raw.starts_with('\'') && !raw.starts_with("\"~")
+}
+
+/// Quotes a word only when it couldn't be split back into the same word otherwise.
+fn requote(word: &str) -> String {
+    if !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\'))
+    {
+        return word.to_string();
+    }
+
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+fn is_relative_path(word: &str) -> bool {
+    word == "."
+        || word == ".."
+        || word.starts_with("./")
+        || word.starts_with("../")
+        || (word.contains('/') && !word.starts_with(['$', '~']))
+}
+
+fn trim_trailing_slashes(word: &str) -> &str {
+    match word.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Removes `.` components, `..` components where possible, duplicate and trailing slashes.
+fn clean(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !cleaned.pop() {
+                    cleaned.push("..");
+                }
+            }
+            component => cleaned.push(component),
+        }
+    }
+
+    if cleaned.as_os_str().is_empty() {
+        cleaned.push(".");
+    }
+    cleaned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathNormalizer;
+    use std::path::Path;
+
+    #[test]
+    fn it_expands_home_and_trailing_slashes() {
+        let paths = PathNormalizer::default().with_home(Path::new("/home/me"));
+
+        for typed in [
+            "cd ~/work/projects",
+            "cd /home/me/work/projects",
+            "cd $HOME/work/projects/",
+            "cd \"${HOME}/work//projects\"",
+            "cd /home/me/work/./projects",
+        ] {
+            assert_eq!(paths.normalize_command(typed), "cd /home/me/work/projects");
+        }
+
+        assert_eq!(paths.normalize_command("cd ~"), "cd /home/me");
+        assert_eq!(paths.normalize_command("ls /"), "ls /");
+        assert_eq!(paths.normalize_command("ls -la src/"), "ls -la src");
+        assert_eq!(
+            paths.normalize_command("curl https://example.com/"),
+            "curl https://example.com/"
+        );
+        assert_eq!(paths.normalize_command("echo $HOMEWORK"), "echo $HOMEWORK");
+        assert_eq!(paths.normalize_command("ls $PWD/src"), "ls $PWD/src");
+        assert_eq!(paths.normalize_command("ls '~/src'"), "ls ~/src");
+    }
+
+    #[test]
+    fn it_keeps_quoted_words_whole() {
+        let paths = PathNormalizer::default().with_home(Path::new("/home/me"));
+
+        assert_eq!(
+            paths.normalize_command("git checkout 'main'"),
+            "git checkout main"
+        );
+        assert_eq!(paths.normalize_command("echo \"a b\""), "echo 'a b'");
+        assert_ne!(
+            paths.normalize_command("echo \"a b\""),
+            paths.normalize_command("echo a b")
+        );
+        assert_eq!(
+            paths.normalize_command("cp \"$HOME/my notes\" ."),
+            "cp '/home/me/my notes' ."
+        );
+    }
+
+    #[test]
+    fn it_resolves_relative_paths_against_cwd() {
+        let paths = PathNormalizer::default()
+            .with_home(Path::new("/home/me"))
+            .with_cwd(Path::new("/home/me/work"));
+
+        assert_eq!(
+            paths.normalize_command("cd projects/"),
+            "cd /home/me/work/projects"
+        );
+        assert_eq!(paths.normalize_command("cd .."), "cd /home/me");
+        assert_eq!(paths.normalize_command("cd ./"), "cd /home/me/work");
+        assert_eq!(
+            paths.normalize_command("cd projects"),
+            "cd /home/me/work/projects"
+        );
+        assert_eq!(paths.normalize_command("ls projects"), "ls projects");
+        assert_eq!(paths.normalize_command("git status"), "git status");
+    }
+}
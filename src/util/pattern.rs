@@ -0,0 +1,392 @@
+use super::alias::*;
+use super::config::*;
+use super::log::*;
+use super::macros::*;
+use fancy_regex::{escape, Regex};
+use std::{
+    cell::OnceCell,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// A part of an alias body that is only known when the alias runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `$(command)`, as written.
+    Substitution(String),
+    /// `$NAME` or `${NAME}`, by name.
+    Variable(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// An alias body split into words, where placeholders stand for whatever the user typed in their place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasPattern {
+    words: Vec<Vec<Segment>>,
+}
+
+/// An [AliasPattern] whose placeholders were resolved, with a regex for each word.
+#[derive(Debug, Clone)]
+pub struct CompiledPattern {
+    words: Vec<Regex>,
+}
+
+/// Computes the value of a placeholder from the environment alias-helper runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolver {
+    /// The branch checked out in the current directory, read from `.git/HEAD`.
+    GitBranch,
+    /// The value of the variable in alias-helper's environment.
+    Env,
+    /// Whatever single word was typed in its place.
+    Any,
+}
+
+/// How each placeholder is evaluated, configured by `placeholder_resolvers`. A placeholder without
+/// a resolver, or whose resolver finds no value, can't be matched.
+#[derive(Debug, Clone, Default)]
+pub struct Resolvers {
+    rules: Vec<(Placeholder, Resolver)>,
+    cwd: Option<PathBuf>,
+    /// The branch, read once however many aliases ask for it.
+    branch: OnceCell<Option<String>>,
+}
+
+impl AliasPattern {
+    pub fn parse(command: &str) -> AliasPattern {
+        let words = split_words(command)
+            .iter()
+            .map(|word| parse_segments(&unquote(word).unwrap_or_else(|_| word.to_owned())))
+            .collect();
+
+        AliasPattern { words }
+    }
+
+    pub fn has_placeholders(&self) -> bool {
+        self.words
+            .iter()
+            .flatten()
+            .any(|segment| matches!(segment, Segment::Placeholder(_)))
+    }
+
+    /// Whether `typed` could be what this alias runs, with every placeholder resolved to `Any`
+    /// matching at least one character of a single word.
+    pub fn matches(&self, typed: &str, resolvers: &Resolvers) -> bool {
+        self.compile(resolvers)
+            .is_some_and(|pattern| pattern.matches(typed))
+    }
+
+    /// Resolves the placeholders and builds the regexes, so the pattern can be matched against
+    /// many commands. Returns `None` if a placeholder is unresolved or a regex can't be built.
+    pub fn compile(&self, resolvers: &Resolvers) -> Option<CompiledPattern> {
+        let words = self
+            .words
+            .iter()
+            .map(|segments| {
+                let pattern: String = segments
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Literal(text) => Some(escape(text).into_owned()),
+                        Segment::Placeholder(placeholder) if resolvers.matches_any(placeholder) => {
+                            Some("(.+?)".to_string())
+                        }
+                        Segment::Placeholder(placeholder) => resolvers
+                            .resolve(placeholder)
+                            .map(|value| escape(&value).into_owned()),
+                    })
+                    .collect::<Option<String>>()?;
+
+                let pattern = format!("^{}$", pattern);
+                Regex::new(&pattern)
+                    .map_err(|err| {
+                        ErrorCode::RegexParse(&pattern, err).log_debug(function_name!());
+                    })
+                    .ok()
+            })
+            .collect::<Option<Vec<Regex>>>()?;
+
+        Some(CompiledPattern { words })
+    }
+}
+
+impl CompiledPattern {
+    /// Whether `typed` has a matching word for each word of the pattern.
+    pub fn matches(&self, typed: &str) -> bool {
+        let typed: Vec<String> = split_words(typed)
+            .iter()
+            .map(|word| unquote(word).unwrap_or_else(|_| word.to_owned()))
+            .collect();
+
+        typed.len() == self.words.len()
+            && self.words.iter().zip(&typed).all(|(regex, word)| {
+                regex.is_match(word).unwrap_or_else(|err| {
+                    ErrorCode::RegexValidationMatch(regex, word, err).log_debug(function_name!());
+                    false
+                })
+            })
+    }
+}
+
+impl Resolvers {
+    pub fn new(rules: Vec<(Placeholder, Resolver)>) -> Resolvers {
+        Resolvers {
+            rules,
+            cwd: env::current_dir().ok(),
+            branch: OnceCell::new(),
+        }
+    }
+
+    /// Reads `placeholder_resolvers` entries such as `$(git_current_branch)=git-branch`.
+    pub fn from_config(config: &Config) -> Result<Resolvers, ConfigError> {
+        let invalid = || {
+            ConfigError::InvalidValue(
+                "placeholder_resolvers".to_string(),
+                config.source("placeholder_resolvers").to_owned(),
+            )
+        };
+        let mut rules = vec![];
+
+        for entry in &config.placeholder_resolvers {
+            let (placeholder, resolver) = entry.rsplit_once('=').ok_or_else(invalid)?;
+            let placeholder = match parse_segments(placeholder.trim())[..] {
+                [Segment::Placeholder(ref placeholder)] => placeholder.to_owned(),
+                _ => return Err(invalid()),
+            };
+
+            rules.push((placeholder, resolver.trim().parse().map_err(|_| invalid())?));
+        }
+
+        Ok(Resolvers::new(rules))
+    }
+
+    /// Resolves relative to `cwd` instead of the current directory.
+    pub fn with_cwd(self, cwd: &Path) -> Resolvers {
+        Resolvers {
+            cwd: Some(cwd.to_owned()),
+            branch: OnceCell::new(),
+            ..self
+        }
+    }
+
+    /// Whether the placeholder stands for any word rather than a value.
+    pub fn matches_any(&self, placeholder: &Placeholder) -> bool {
+        self.rules
+            .iter()
+            .any(|(rule, resolver)| rule == placeholder && *resolver == Resolver::Any)
+    }
+
+    /// The value of the placeholder, or `None` when it has none.
+    pub fn resolve(&self, placeholder: &Placeholder) -> Option<String> {
+        let (_, resolver) = self.rules.iter().find(|(rule, _)| rule == placeholder)?;
+
+        let value = match (resolver, placeholder) {
+            (Resolver::GitBranch, _) => self
+                .branch
+                .get_or_init(|| git_branch(self.cwd.as_deref()?))
+                .to_owned(),
+            (Resolver::Env, Placeholder::Variable(name)) => env::var(name).ok(),
+            (Resolver::Env, Placeholder::Substitution(_)) | (Resolver::Any, _) => None,
+        };
+
+        debug_value!(placeholder, value);
+        value
+    }
+}
+
+/// Splits a word into literals and placeholders. A `$` that starts neither is a literal.
+fn parse_segments(word: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut literal = String::new();
+    let mut rest = word;
+
+    while let Some(index) = rest.find('$') {
+        literal.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+
+        let placeholder = if let Some(inner) = after.strip_prefix('(') {
+            closing_paren(inner).map(|end| {
+                let source = format!("$({}", &inner[..=end]);
+                (Placeholder::Substitution(source), &inner[end + 1..])
+            })
+        } else if let Some(inner) = after.strip_prefix('{') {
+            inner
+                .split_once('}')
+                .map(|(name, rest)| (Placeholder::Variable(name.to_string()), rest))
+        } else {
+            let end = after
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(after.len());
+            let name = &after[..end];
+
+            name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                .then(|| (Placeholder::Variable(name.to_string()), &after[end..]))
+        };
+
+        match placeholder {
+            Some((placeholder, remainder)) => {
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Placeholder(placeholder));
+                rest = remainder;
+            }
+            None => {
+                literal.push('$');
+                rest = after;
+            }
+        }
+    }
+
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    segments
+}
+
+/// The index of the parenthesis that closes one already opened, in `text`.
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The branch checked out in the repository containing `dir`, following `.git` files of worktrees.
+fn git_branch(dir: &Path) -> Option<String> {
+    let git = dir
+        .ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|git| git.exists())?;
+
+    let git_dir = if git.is_file() {
+        let content = fs::read_to_string(&git).ok()?;
+        let path = PathBuf::from(content.trim().strip_prefix("gitdir:")?.trim());
+        if path.is_relative() {
+            git.parent()?.join(path)
+        } else {
+            path
+        }
+    } else {
+        git
+    };
+
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(str::to_string)
+}
+
+impl FromStr for Resolver {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "git-branch" => Ok(Resolver::GitBranch),
+            "env" => Ok(Resolver::Env),
+            "any" => Ok(Resolver::Any),
+            _ => Err(format!(
+                "unsupported resolver {:?}, expected git-branch, env or any",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolver::GitBranch => write!(f, "git-branch"),
+            Resolver::Env => write!(f, "env"),
+            Resolver::Any => write!(f, "any"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AliasPattern, Placeholder, Resolver, Resolvers};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_matches_placeholders_as_wildcards() {
+        let resolvers = Resolvers::new(vec![
+            (
+                Placeholder::Substitution("$(git_current_branch)".to_string()),
+                Resolver::Any,
+            ),
+            (Placeholder::Variable("PROJECTS".to_string()), Resolver::Any),
+        ]);
+        let ggpush = AliasPattern::parse("git push origin \"$(git_current_branch)\"");
+
+        assert!(ggpush.has_placeholders());
+        assert!(ggpush.matches("git push origin feature-x", &resolvers));
+        assert!(ggpush.matches("git push origin 'feature-x'", &resolvers));
+        assert!(!ggpush.matches("git push origin", &resolvers));
+        assert!(!ggpush.matches("git push upstream feature-x", &resolvers));
+
+        let cd = AliasPattern::parse("cd ${PROJECTS}/src");
+        assert!(cd.matches("cd ~/code/src", &resolvers));
+        assert!(!cd.matches("cd /src", &resolvers));
+
+        let literal = AliasPattern::parse("echo $ 5$");
+        assert!(!literal.has_placeholders());
+        assert!(literal.matches("echo $ 5$", &resolvers));
+    }
+
+    #[test]
+    fn it_does_not_match_unresolved_placeholders() {
+        let resolvers = Resolvers::default();
+
+        assert!(!AliasPattern::parse("$EDITOR").matches("vim", &resolvers));
+        assert!(
+            !AliasPattern::parse("git push origin $(git_current_branch)")
+                .matches("git push origin main", &resolvers)
+        );
+    }
+
+    #[test]
+    fn it_resolves_placeholders() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("repo");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(".git/HEAD"), "ref: refs/heads/feature-x\n").unwrap();
+
+        let branch = Placeholder::Substitution("$(git_current_branch)".to_string());
+        let resolvers = Resolvers::new(vec![(branch.to_owned(), Resolver::GitBranch)])
+            .with_cwd(&dir.join("src"));
+        let ggpush = AliasPattern::parse("git push origin \"$(git_current_branch)\"");
+
+        assert_eq!(resolvers.resolve(&branch), Some("feature-x".to_string()));
+        assert!(ggpush.matches("git push origin feature-x", &resolvers));
+        assert!(!ggpush.matches("git push origin main", &resolvers));
+
+        // The branch is read once for all the aliases matched in a call.
+        fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        assert_eq!(resolvers.resolve(&branch), Some("feature-x".to_string()));
+
+        let unknown = Resolvers::new(vec![(
+            Placeholder::Variable("PATTERN_TEST_UNSET_VARIABLE".to_string()),
+            Resolver::Env,
+        )]);
+        assert!(
+            !AliasPattern::parse("echo $PATTERN_TEST_UNSET_VARIABLE").matches("echo x", &unknown)
+        );
+    }
+}
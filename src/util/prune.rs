@@ -0,0 +1,276 @@
+use super::alias::*;
+use super::history::HistoryEntry;
+use super::log::*;
+use super::macros::*;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+/// An alias that was neither typed nor reached through another alias that was typed.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadAlias {
+    pub alias: Alias,
+    /// The aliases whose commands call this one, which would break if it were removed alone.
+    pub dependents: Vec<String>,
+}
+
+/// Finds the aliases of `aliases` that no command in `entries` used, directly or through the
+/// commands of the aliases it did use, in the order they were defined.
+pub fn find_dead_aliases(entries: &[HistoryEntry], aliases: &[Alias]) -> Vec<DeadAlias> {
+    let names: HashSet<&str> = aliases
+        .iter()
+        .map(|alias| alias.name.get().as_str())
+        .collect();
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut pending: Vec<String> = entries
+        .iter()
+        .flat_map(|entry| command_words(&entry.command))
+        .filter(|word| names.contains(word.as_str()))
+        .collect();
+
+    // An alias whose command calls another alias keeps that one alive too.
+    while let Some(name) = pending.pop() {
+        if !used.insert(name.to_owned()) {
+            continue;
+        }
+
+        pending.extend(
+            aliases
+                .iter()
+                .filter(|alias| alias.name.get() == &name)
+                .flat_map(|alias| command_words(alias.command.get()))
+                .filter(|word| names.contains(word.as_str()) && !used.contains(word)),
+        );
+    }
+    debug_value!(used);
+
+    aliases
+        .iter()
+        .filter(|alias| !used.contains(alias.name.get()))
+        .map(|alias| {
+            let mut dependents: Vec<String> = vec![];
+            for other in aliases.iter().filter(|other| other.name != alias.name) {
+                let name = other.name.to_string();
+                if command_words(other.command.get()).contains(alias.name.get())
+                    && !dependents.contains(&name)
+                {
+                    dependents.push(name);
+                }
+            }
+
+            DeadAlias {
+                alias: alias.to_owned(),
+                dependents,
+            }
+        })
+        .collect()
+}
+
+/// A unified diff, without context lines, that removes the definitions of `dead` from the files
+/// they were read from. Aliases that didn't come from a file are listed in a leading comment.
+pub fn removal_patch(dead: &[DeadAlias]) -> String {
+    let mut patch = String::new();
+    let mut files: BTreeMap<PathBuf, BTreeMap<usize, Vec<&str>>> = BTreeMap::new();
+
+    for dead in dead {
+        match &dead.alias.origin {
+            Some(Origin {
+                file: Some(file),
+                line,
+                text,
+                ..
+            }) => files
+                .entry(file.to_owned())
+                .or_default()
+                .entry(*line)
+                .or_default()
+                .push(text),
+            _ => patch += &format!("# {} was not read from a file\n", dead.alias),
+        }
+    }
+
+    for (file, lines) in files {
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(err) => {
+                debug!("[{}] cannot read {:?}: {}", function_name!(), file, err);
+                patch += &format!("# {} could not be read\n", file.display());
+                continue;
+            }
+        };
+        let content: Vec<&str> = content.lines().collect();
+
+        patch += &format!("--- {}\n+++ {}\n", file.display(), file.display());
+        let mut removed = 0;
+        for (line, texts) in lines {
+            // A definition whose quotes span several lines takes all of them.
+            let span = texts
+                .iter()
+                .map(|text| text.lines().count())
+                .max()
+                .unwrap_or(1)
+                .max(1);
+            let Some(originals) = line
+                .checked_sub(1)
+                .and_then(|index| content.get(index..index + span))
+            else {
+                debug!("[{}] no line {} in {:?}", function_name!(), line, file);
+                continue;
+            };
+            let original = originals.join("\n");
+
+            let mut remainder = original.to_string();
+            for text in texts {
+                remainder = remainder.replacen(text, "", 1);
+            }
+
+            if matches!(remainder.trim(), "" | "alias" | "alias --") {
+                patch += &format!("@@ -{},{} +{},0 @@\n", line, span, line - 1 - removed);
+                for original in originals {
+                    patch += &format!("-{}\n", original);
+                }
+                removed += span;
+            } else if span > 1 {
+                patch += &format!(
+                    "# {}:{} defines other aliases across several lines, edit it by hand\n",
+                    file.display(),
+                    line
+                );
+            } else {
+                let remainder: Vec<&str> = remainder.split_whitespace().collect();
+                let indent: String = original.chars().take_while(|c| c.is_whitespace()).collect();
+                patch += &format!(
+                    "@@ -{},1 +{},1 @@\n-{}\n+{}{}\n",
+                    line,
+                    line - removed,
+                    original,
+                    indent,
+                    remainder.join(" ")
+                );
+            }
+        }
+    }
+
+    patch
+}
+
+/// The words of `command` in command position: its first word and those following an operator,
+/// which is where the shell expands aliases.
+fn command_words(command: &str) -> Vec<String> {
+    let mut words = vec![];
+
+    for line in command.lines() {
+        let mut expect_command = true;
+        for word in split_words(line) {
+            let ends_command = word.starts_with(['|', '&', ';', '(']) || word.ends_with(';');
+            if expect_command && !ends_command {
+                words.push(word.trim_end_matches(';').to_owned());
+            }
+            expect_command = ends_command;
+        }
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_dead_aliases, removal_patch};
+    use crate::{Alias, HistoryEntry, RcReader};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn entries(commands: &[&str]) -> Vec<HistoryEntry> {
+        commands
+            .iter()
+            .map(|command| HistoryEntry {
+                timestamp: None,
+                command: command.to_string(),
+                cwd: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_finds_aliases_used_neither_directly_nor_through_another() {
+        let aliases: Vec<Alias> = [
+            "g=git",
+            "gst='g status'",
+            "gco='git checkout'",
+            "gcom='gco main'",
+            "l='ls -l'",
+        ]
+        .into_iter()
+        .map(|line| Alias::from(line).unwrap())
+        .collect();
+
+        let dead = find_dead_aliases(&entries(&["gst", "echo l | l"]), &aliases);
+        let summary: Vec<(String, Vec<String>)> = dead
+            .iter()
+            .map(|dead| (dead.alias.name.to_string(), dead.dependents.to_owned()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("gco".to_string(), vec!["gcom".to_string()]),
+                ("gcom".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_writes_a_patch_removing_dead_aliases() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        let rc = dir.join(".zshrc");
+        fs::write(
+            &rc,
+            "alias g=git\nalias gco='git checkout'\nalias l='ls -l' ll='ls -la'\nexport EDITOR=vim\n",
+        )
+        .unwrap();
+
+        let aliases = RcReader::read_files(&[rc.to_owned()]).aliases;
+        let dead = find_dead_aliases(&entries(&["g status", "l"]), &aliases);
+        assert_eq!(
+            removal_patch(&dead),
+            format!(
+                "--- {0}\n+++ {0}\n@@ -2,1 +1,0 @@\n-alias gco='git checkout'\n@@ -3,1 +2,1 @@\n-alias l='ls -l' ll='ls -la'\n+alias l='ls -l'\n",
+                rc.display()
+            )
+        );
+    }
+
+    #[test]
+    fn it_removes_every_line_of_a_multi_line_definition() {
+        let temp = TempDir::new().unwrap();
+        let rc = temp.path().join(".bashrc");
+        fs::write(
+            &rc,
+            "alias g=git\nalias greet='echo hello\necho world'\nalias l='ls -l'\n",
+        )
+        .unwrap();
+
+        let aliases = RcReader::read_files(&[rc.to_owned()]).aliases;
+        let mut dead = find_dead_aliases(&entries(&["g status"]), &aliases);
+        assert_eq!(
+            removal_patch(&dead),
+            format!(
+                "--- {0}\n+++ {0}\n@@ -2,2 +1,0 @@\n-alias greet='echo hello\n-echo world'\n@@ -4,1 +1,0 @@\n-alias l='ls -l'\n",
+                rc.display()
+            )
+        );
+
+        // Origins without a line are left alone rather than underflowing.
+        for dead in &mut dead {
+            dead.alias.origin.as_mut().unwrap().line = 0;
+        }
+        assert_eq!(
+            removal_patch(&dead),
+            format!("--- {0}\n+++ {0}\n", rc.display())
+        );
+    }
+}
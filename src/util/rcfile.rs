@@ -0,0 +1,757 @@
+use super::alias::*;
+use super::ignore::{expand_home, marked_lines};
+use super::log::*;
+use super::macros::*;
+use super::shell::Shell;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// A line the reader could not evaluate without running the shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLine {
+    pub file: PathBuf,
+    pub line: usize,
+    pub text: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// `eval` runs code that only exists at runtime.
+    Eval,
+    /// A `source` path depends on a command substitution, a glob or an unknown variable.
+    DynamicPath,
+    /// A `source`d file does not exist or cannot be read.
+    Unreadable,
+    /// A file `source`s itself, directly or through other files.
+    IncludeLoop,
+    /// The quotes opened on this line are never closed.
+    UnterminatedQuote,
+    /// The alias definition was rejected.
+    InvalidAlias(AliasError),
+}
+
+/// The aliases found in a set of rc files, and the lines that were skipped along the way.
+#[derive(Debug, Clone, Default)]
+pub struct RcScan {
+    pub aliases: Vec<Alias>,
+    pub skipped: Vec<SkippedLine>,
+}
+
+/// A word as written, with quotes, and the byte range it spans in the file.
+#[derive(Debug, Clone)]
+struct Word {
+    raw: String,
+    start: usize,
+    end: usize,
+}
+
+/// A file being read, used to locate words in it.
+struct Source<'a> {
+    path: &'a Path,
+    code: &'a str,
+    /// The lines whose definitions carry an ignore marker.
+    marked: HashSet<usize>,
+    /// The byte offset each line starts at.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Source<'a> {
+    fn new(path: &'a Path, code: &'a str) -> Source<'a> {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Source {
+            path,
+            code,
+            marked: marked_lines(code),
+            line_starts,
+        }
+    }
+
+    /// The line and column of a byte offset, both counting from 1.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let column = self.code[self.line_starts[line - 1]..offset]
+            .chars()
+            .count()
+            + 1;
+        (line, column)
+    }
+
+    /// The text of a line, counting from 1, without its newline.
+    fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.code.len(), |end| end - 1);
+        &self.code[start..end]
+    }
+
+    /// The start of the first `alias` statement on a line after the one `offset` is on.
+    fn next_definition(&self, offset: usize) -> Option<usize> {
+        let (line, _) = self.position(offset);
+
+        (line + 1..=self.line_starts.len())
+            .find(|&line| {
+                let text = self.line(line).trim_start();
+                text.strip_prefix("alias")
+                    .is_some_and(|rest| rest.starts_with(char::is_whitespace))
+            })
+            .map(|line| self.line_starts[line - 1])
+    }
+}
+
+/// Reads aliases out of shell rc files without running them.
+///
+/// `alias` statements are collected wherever they appear, including inside conditionals,
+/// and `source`/`.` includes are followed, as are the plugins Oh-My-Zsh would load.
+#[derive(Debug, Default)]
+pub struct RcReader {
+    scan: RcScan,
+    variables: HashMap<String, String>,
+    plugins: Vec<String>,
+    stack: Vec<PathBuf>,
+    visited: HashSet<PathBuf>,
+    /// The plugin whose files are being read.
+    plugin: Option<String>,
+}
+
+impl RcReader {
+    pub fn new() -> RcReader {
+        RcReader::default()
+    }
+
+    /// Reads every file in order, skipping the ones that don't exist.
+    pub fn read_files(paths: &[PathBuf]) -> RcScan {
+        let mut reader = RcReader::new();
+
+        for path in paths {
+            if path.is_file() {
+                reader.read_file(path);
+            } else {
+                trace!("[{}] {:?} does not exist, skipping", function_name!(), path);
+            }
+        }
+
+        reader.finish()
+    }
+
+    pub fn finish(self) -> RcScan {
+        debug_value!(self);
+        self.scan
+    }
+
+    /// Reads a file and everything it includes.
+    pub fn read_file(&mut self, path: &Path) {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+
+        if self.stack.contains(&key) {
+            trace!("[{}] {:?} includes itself", function_name!(), path);
+            return;
+        }
+
+        if !self.visited.insert(key.to_owned()) {
+            trace!("[{}] already read {:?}, skipping", function_name!(), path);
+            return;
+        }
+
+        let content = match fs::read(path) {
+            Ok(content) => String::from_utf8_lossy(&content).into_owned(),
+            Err(err) => {
+                debug!("[{}] cannot read {:?}: {}", function_name!(), path, err);
+                return;
+            }
+        };
+
+        self.stack.push(key);
+        self.read_str(path, &content);
+        self.stack.pop();
+    }
+
+    /// Reads shell code as if it were the content of the file at `path`.
+    pub fn read_str(&mut self, path: &Path, code: &str) {
+        let source = Source::new(path, code);
+        let mut from = 0;
+
+        loop {
+            let (statements, unterminated) = split_statements(code, from);
+
+            for statement in statements {
+                self.evaluate(&source, &statement);
+            }
+
+            let Some(offset) = unterminated else {
+                break;
+            };
+            self.skip(&source, offset, SkipReason::UnterminatedQuote);
+
+            // The quote swallowed the rest of the file, so carry on from the next definition.
+            match source.next_definition(offset) {
+                Some(next) => from = next,
+                None => break,
+            }
+        }
+    }
+
+    fn evaluate(&mut self, source: &Source, statement: &[Word]) {
+        let mut words = statement;
+
+        while let [keyword, rest @ ..] = words {
+            match keyword.raw.as_str() {
+                "then" | "else" | "do" | "{" | "}" | "!" | "command" | "builtin" => words = rest,
+                _ => break,
+            }
+        }
+
+        let Some((keyword, arguments)) = words.split_first() else {
+            return;
+        };
+
+        match keyword.raw.as_str() {
+            "alias" => self.define(source, arguments),
+            "source" | "." => {
+                if let Some(word) = arguments.first() {
+                    self.include(source, keyword.start, &word.raw);
+                }
+            }
+            "eval" => self.skip(source, keyword.start, SkipReason::Eval),
+            "export" | "typeset" | "declare" | "local" | "readonly" => {
+                for assignment in arguments.iter().filter(|word| !word.raw.starts_with('-')) {
+                    self.assign(&assignment.raw);
+                }
+            }
+            _ if arguments.is_empty() && is_assignment(&keyword.raw) => self.assign(&keyword.raw),
+            _ => {}
+        }
+    }
+
+    /// Handles `alias name=value...` in zsh and bash, and `alias name value` in fish.
+    fn define(&mut self, source: &Source, arguments: &[Word]) {
+        let arguments: Vec<&Word> = arguments
+            .iter()
+            .skip_while(|word| word.raw.starts_with('-'))
+            .collect();
+
+        // (name, value, start, end)
+        let definitions: Vec<(&str, &str, usize, usize)> = match arguments[..] {
+            [name, value] if !name.raw.contains('=') => {
+                vec![(&name.raw, &value.raw, name.start, value.end)]
+            }
+            _ => arguments
+                .iter()
+                .filter_map(|word| {
+                    let (name, value) = word.raw.split_once('=')?;
+                    Some((name, value, word.start, word.end))
+                })
+                .collect(),
+        };
+
+        for (name, value, start, end) in definitions {
+            let alias = unquote(name).and_then(|name| {
+                let value = if value.is_empty() {
+                    String::new()
+                } else {
+                    unquote(value)?
+                };
+                let shell = Shell::for_rc_file(source.path);
+                Ok(Alias::new(
+                    Name::for_shell(&name, shell)?,
+                    Command::new(&value)?,
+                ))
+            });
+
+            let alias = match alias {
+                Ok(alias) => alias,
+                Err(err) => {
+                    self.skip(source, start, SkipReason::InvalidAlias(err));
+                    continue;
+                }
+            };
+
+            let (line, column) = source.position(start);
+            self.scan.aliases.push(alias.with_origin(Origin {
+                kind: match self.plugin {
+                    Some(_) => SourceKind::Plugin,
+                    None => SourceKind::RcFile,
+                },
+                file: Some(source.path.to_owned()),
+                line,
+                column,
+                plugin: self.plugin.to_owned(),
+                text: source.code[start..end].to_string(),
+                marked: source.marked.contains(&line),
+            }));
+        }
+    }
+
+    fn include(&mut self, source: &Source, offset: usize, word: &str) {
+        let Some(target) = self.expand(word) else {
+            self.skip(source, offset, SkipReason::DynamicPath);
+            return;
+        };
+
+        let mut target = expand_home(&target);
+        if target.is_relative() {
+            let sibling = source.path.parent().map(|dir| dir.join(&target));
+            let from_home = env::var_os("HOME").map(|home| Path::new(&home).join(&target));
+            target = [sibling, from_home]
+                .into_iter()
+                .flatten()
+                .find(|candidate| candidate.is_file())
+                .unwrap_or(target);
+        }
+
+        if target
+            .file_name()
+            .is_some_and(|name| name == "oh-my-zsh.sh")
+        {
+            self.read_oh_my_zsh(&target);
+            return;
+        }
+
+        let key = fs::canonicalize(&target).unwrap_or_else(|_| target.to_owned());
+        if self.stack.contains(&key) {
+            self.skip(source, offset, SkipReason::IncludeLoop);
+        } else if !target.is_file() {
+            self.skip(source, offset, SkipReason::Unreadable);
+        } else {
+            self.read_file(&target);
+        }
+    }
+
+    /// Reads what `oh-my-zsh.sh` would load: its `lib` files, the enabled plugins and the custom files.
+    fn read_oh_my_zsh(&mut self, entrypoint: &Path) {
+        let Some(root) = entrypoint.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let custom = self
+            .variables
+            .get("ZSH_CUSTOM")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| root.join("custom"));
+
+        let previous = self.plugin.replace("oh-my-zsh".to_string());
+        for file in zsh_files_in(&root.join("lib")) {
+            self.read_file(&file);
+        }
+
+        let plugins = self.plugins.clone();
+        for plugin in plugins {
+            let file_name = format!("{plugin}.plugin.zsh");
+            let file = [custom.join("plugins"), root.join("plugins")]
+                .into_iter()
+                .map(|dir| dir.join(&plugin).join(&file_name))
+                .find(|file| file.is_file());
+
+            match file {
+                Some(file) => {
+                    self.plugin = Some(plugin);
+                    self.read_file(&file);
+                }
+                None => debug!("[{}] cannot find plugin {:?}", function_name!(), plugin),
+            }
+        }
+        self.plugin = previous;
+
+        for file in zsh_files_in(&custom) {
+            self.read_file(&file);
+        }
+    }
+
+    /// Records `NAME=value`, and the `plugins=(...)` array Oh-My-Zsh reads.
+    fn assign(&mut self, assignment: &str) {
+        let Some((name, value)) = assignment.split_once('=') else {
+            return;
+        };
+
+        if name == "plugins" {
+            if let Some(list) = value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+                self.plugins = list.split_whitespace().map(str::to_string).collect();
+            }
+        } else if let Some(value) = self.expand(value) {
+            self.variables.insert(name.to_string(), value);
+        }
+    }
+
+    /// Unquotes a word and expands the variables it refers to, when they are known statically.
+    fn expand(&self, word: &str) -> Option<String> {
+        if word.contains("$(") || word.contains('`') || word.contains(['*', '?', '[']) {
+            return None;
+        }
+
+        let word = unquote(word).ok()?;
+        let mut output = String::new();
+        let mut rest = word.as_str();
+
+        while let Some(index) = rest.find('$') {
+            output.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+
+            let (name, remainder) = match rest.strip_prefix('{') {
+                Some(braced) => braced.split_once('}')?,
+                None => {
+                    let end = rest
+                        .find(|c: char| !c.is_alphanumeric() && c != '_')
+                        .unwrap_or(rest.len());
+                    (&rest[..end], &rest[end..])
+                }
+            };
+
+            let value = self
+                .variables
+                .get(name)
+                .cloned()
+                .or_else(|| env::var(name).ok())?;
+            output.push_str(&value);
+            rest = remainder;
+        }
+
+        output.push_str(rest);
+        Some(output)
+    }
+
+    fn skip(&mut self, source: &Source, offset: usize, reason: SkipReason) {
+        let (line, _) = source.position(offset);
+        let text = source.line(line).trim().to_string();
+        debug!(
+            "[{}] skipping {}:{} {:?}: {}",
+            function_name!(),
+            source.path.display(),
+            line,
+            text,
+            reason
+        );
+
+        self.scan.skipped.push(SkippedLine {
+            file: source.path.to_owned(),
+            line,
+            text,
+            reason,
+        });
+    }
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+fn zsh_files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zsh"))
+        .collect();
+
+    files.sort();
+    files
+}
+
+/// Splits shell code into statements of words, dropping comments.
+///
+/// Statements end at newlines, `;`, `&` and `|` outside of quotes and parentheses.
+/// Starts at the byte offset `from`, and also returns the offset of a quote left open at the end
+/// of the code.
+fn split_statements(code: &str, from: usize) -> (Vec<Vec<Word>>, Option<usize>) {
+    let mut statements = vec![];
+    let mut words: Vec<Word> = vec![];
+    let mut word: Option<Word> = None;
+    let mut quote: Option<(char, usize)> = None;
+    let mut depth = 0;
+    let mut chars = code[from..]
+        .char_indices()
+        .map(|(index, c)| (from + index, c))
+        .peekable();
+
+    macro_rules! push {
+        ($index:expr, $c:expr) => {
+            word.get_or_insert_with(|| Word {
+                raw: String::new(),
+                start: $index,
+                end: $index,
+            })
+            .raw
+            .push($c)
+        };
+    }
+
+    macro_rules! end_word {
+        ($index:expr) => {
+            if let Some(mut word) = word.take() {
+                word.end = $index;
+                words.push(word);
+            }
+        };
+    }
+
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (Some((open, _)), c) if c == open => {
+                quote = None;
+                push!(index, c);
+            }
+            (Some(('\'', _)), c) => push!(index, c),
+            (_, '\\') => match chars.next() {
+                Some((_, '\n')) => {}
+                Some((_, next)) => {
+                    push!(index, c);
+                    push!(index, next);
+                }
+                None => push!(index, c),
+            },
+            (Some(_), c) => push!(index, c),
+            (None, '\'' | '"') => {
+                quote = Some((c, index));
+                push!(index, c);
+            }
+            (None, '#') if word.is_none() => {
+                while chars.next_if(|&(_, next)| next != '\n').is_some() {}
+            }
+            (None, '(') => {
+                depth += 1;
+                push!(index, c);
+            }
+            (None, ')') if depth > 0 => {
+                depth -= 1;
+                push!(index, c);
+            }
+            (None, '\n') if depth > 0 => push!(index, ' '),
+            (None, c) if depth > 0 => push!(index, c),
+            (None, '\n' | ';' | '&' | '|') => {
+                depth = 0;
+                end_word!(index);
+                if !words.is_empty() {
+                    statements.push(std::mem::take(&mut words));
+                }
+            }
+            (None, c) if c.is_whitespace() => end_word!(index),
+            (None, c) => push!(index, c),
+        }
+    }
+
+    if let Some((_, open)) = quote {
+        return (statements, Some(open));
+    }
+
+    end_word!(code.len());
+    if !words.is_empty() {
+        statements.push(words);
+    }
+    (statements, None)
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Eval => write!(f, "eval cannot be evaluated statically"),
+            SkipReason::DynamicPath => write!(f, "the sourced path is only known at runtime"),
+            SkipReason::Unreadable => write!(f, "the sourced file cannot be read"),
+            SkipReason::IncludeLoop => write!(f, "the file sources itself"),
+            SkipReason::UnterminatedQuote => write!(f, "the quote is never closed"),
+            SkipReason::InvalidAlias(err) => write!(f, "invalid alias: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RcReader, SkipReason};
+    use crate::{Alias, Origin, SourceKind};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_reads_aliases_with_their_origin() {
+        let dir = TempDir::new().unwrap();
+        let rc = dir.path().join("zshrc");
+        fs::write(
+            &rc,
+            "# aliases\n\
+             alias g='git' gb=\"git branch\"\n\
+             \n\
+             if [[ -n $ZSH_VERSION ]]; then alias -g G='| grep'; fi\n\
+             alias multi='echo one\n\
+             two'\n\
+             alias ll 'ls -l'\n",
+        )
+        .unwrap();
+
+        let scan = RcReader::read_files(std::slice::from_ref(&rc));
+
+        assert_eq!(
+            scan.aliases,
+            vec![
+                Alias::from("g='git'").unwrap(),
+                Alias::from("gb='git branch'").unwrap(),
+                Alias::from("G='| grep'").unwrap(),
+                Alias::from("multi='echo one\ntwo'").unwrap(),
+                Alias::from("ll='ls -l'").unwrap(),
+            ]
+        );
+        let lines: Vec<usize> = scan
+            .aliases
+            .iter()
+            .map(|alias| alias.origin.as_ref().unwrap().line)
+            .collect();
+        assert_eq!(lines, vec![2, 2, 4, 5, 7]);
+        assert_eq!(
+            scan.aliases[1].origin,
+            Some(Origin {
+                kind: SourceKind::RcFile,
+                file: Some(rc),
+                line: 2,
+                column: 15,
+                plugin: None,
+                text: "gb=\"git branch\"".to_string(),
+                marked: false,
+            })
+        );
+        assert_eq!(scan.aliases[4].origin.as_ref().unwrap().text, "ll 'ls -l'");
+    }
+
+    #[test]
+    fn it_follows_sources_with_loop_protection() {
+        let dir = TempDir::new().unwrap();
+        let main = dir.path().join("main.sh");
+        let extra = dir.path().join("extra.sh");
+        fs::write(
+            &main,
+            format!(
+                "ALIASES={}\nsource \"$ALIASES\"\n. {}\nalias m='make'\n",
+                extra.display(),
+                main.display()
+            ),
+        )
+        .unwrap();
+        fs::write(
+            &extra,
+            format!("alias e='echo'\nsource {}\n", main.display()),
+        )
+        .unwrap();
+
+        let scan = RcReader::read_files(&[main]);
+
+        assert_eq!(
+            scan.aliases,
+            vec![
+                Alias::from("e='echo'").unwrap(),
+                Alias::from("m='make'").unwrap(),
+            ]
+        );
+        let reasons: Vec<SkipReason> = scan.skipped.into_iter().map(|line| line.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![SkipReason::IncludeLoop, SkipReason::IncludeLoop]
+        );
+    }
+
+    #[test]
+    fn it_reports_what_it_cannot_evaluate() {
+        let dir = TempDir::new().unwrap();
+        let rc = dir.path().join("bashrc");
+        fs::write(
+            &rc,
+            "eval \"$(dircolors)\"\n\
+             source \"$(brew --prefix)/etc/bash_completion\"\n\
+             for f in ~/.aliases/*; do source $f; done\n\
+             source /nonexistent/aliases\n\
+             alias broken=\"oops\n\
+             echo still quoted\n\
+             alias g=git\n\
+             \talias gb='git branch'\n",
+        )
+        .unwrap();
+
+        let scan = RcReader::read_files(&[rc]);
+        assert_eq!(
+            scan.aliases,
+            vec![
+                Alias::from("g=git").unwrap(),
+                Alias::from("gb='git branch'").unwrap(),
+            ]
+        );
+        assert_eq!(scan.aliases[1].origin.as_ref().unwrap().column, 8);
+        let skipped: Vec<(usize, SkipReason)> = scan
+            .skipped
+            .into_iter()
+            .map(|line| (line.line, line.reason))
+            .collect();
+
+        assert_eq!(
+            skipped,
+            vec![
+                (1, SkipReason::Eval),
+                (2, SkipReason::DynamicPath),
+                (3, SkipReason::DynamicPath),
+                (4, SkipReason::Unreadable),
+                (5, SkipReason::UnterminatedQuote),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_loads_oh_my_zsh_plugins() {
+        let dir = TempDir::new().unwrap();
+        let omz = dir.path().join("oh-my-zsh");
+        fs::create_dir_all(omz.join("lib")).unwrap();
+        fs::create_dir_all(omz.join("plugins/git")).unwrap();
+        fs::create_dir_all(omz.join("plugins/docker")).unwrap();
+        fs::write(omz.join("oh-my-zsh.sh"), "eval \"$(something)\"\n").unwrap();
+        fs::write(omz.join("lib/directories.zsh"), "alias md='mkdir -p'\n").unwrap();
+        fs::write(
+            omz.join("plugins/git/git.plugin.zsh"),
+            "alias gst='git status'\n",
+        )
+        .unwrap();
+        fs::write(
+            omz.join("plugins/docker/docker.plugin.zsh"),
+            "alias dps='docker ps'\n",
+        )
+        .unwrap();
+
+        let rc = dir.path().join("zshrc");
+        fs::write(
+            &rc,
+            format!(
+                "export ZSH=\"{}\"\nplugins=(\n  git\n)\nsource $ZSH/oh-my-zsh.sh\n",
+                omz.display()
+            ),
+        )
+        .unwrap();
+
+        let scan = RcReader::read_files(&[rc]);
+
+        assert_eq!(
+            scan.aliases,
+            vec![
+                Alias::from("md='mkdir -p'").unwrap(),
+                Alias::from("gst='git status'").unwrap(),
+            ]
+        );
+        let plugins: Vec<(SourceKind, Option<&str>)> = scan
+            .aliases
+            .iter()
+            .map(|alias| {
+                let origin = alias.origin.as_ref().unwrap();
+                (origin.kind, origin.plugin.as_deref())
+            })
+            .collect();
+        assert_eq!(
+            plugins,
+            vec![
+                (SourceKind::Plugin, Some("oh-my-zsh")),
+                (SourceKind::Plugin, Some("git")),
+            ]
+        );
+        assert!(scan.skipped.is_empty());
+    }
+}
@@ -0,0 +1,651 @@
+use super::alias::*;
+use super::log::*;
+use super::macros::*;
+use std::{fmt, path::Path, str::FromStr};
+
+/// What else the hook collects from the shell on every command, besides its aliases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HookSources {
+    /// The function definitions, to suggest function shortcuts and tell which aliases are broken.
+    pub functions: bool,
+    /// The named directories, which only `zsh` has.
+    pub named_dirs: bool,
+    /// The environment variables, which only `zsh` and `bash` pass.
+    pub env: bool,
+}
+
+/// The shells alias-helper can integrate with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+    Nu,
+}
+
+impl Shell {
+    /// Parses a single alias definition as printed by this shell's `alias` builtin.
+    ///
+    /// `zsh` and `bash` print `name='value'` (`bash` with a leading `alias `),
+    /// `fish` prints `alias name 'value'`, and the nushell hook prints `alias name = value`.
+    pub fn parse_alias(&self, line: &str) -> Result<Alias, AliasError> {
+        debug_value!(line);
+
+        match self {
+            Shell::Zsh | Shell::Bash => Alias::from_shell(line, *self),
+            Shell::Fish => {
+                let definition = line.strip_prefix("alias ").unwrap_or(line).trim_start();
+                let Some((name, value)) = definition.split_once(char::is_whitespace) else {
+                    ErrorCode::InvalidAlias(line.to_string()).log_debug(function_name!());
+                    return Err(AliasError::ParseError(line.to_string()));
+                };
+
+                Ok(Alias::new(
+                    Name::for_shell(name, *self)?,
+                    Command::new(&unquote_fish(value.trim(), line)?)?,
+                ))
+            }
+            Shell::Nu => {
+                let definition = line.strip_prefix("alias ").unwrap_or(line);
+                let Some((name, value)) = definition.split_once('=') else {
+                    ErrorCode::InvalidAlias(line.to_string()).log_debug(function_name!());
+                    return Err(AliasError::ParseError(line.to_string()));
+                };
+
+                Ok(Alias::new(
+                    Name::for_shell(name.trim(), *self)?,
+                    Command::new(value.trim())?,
+                ))
+            }
+        }
+    }
+
+    /// Writes the definition of `alias` the way it would be typed in this shell's rc file.
+    pub fn define_alias(&self, alias: &Alias) -> String {
+        let command = alias.command.get();
+
+        match self {
+            Shell::Zsh | Shell::Bash => {
+                format!("alias {}='{}'", alias.name, command.replace('\'', "'\\''"))
+            }
+            Shell::Fish => format!(
+                "alias {} '{}'",
+                alias.name,
+                command.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+            Shell::Nu => format!("alias {} = {}", alias.name, command),
+        }
+    }
+
+    /// Whether every quote opened in `text` is closed, so a definition printed on several lines is whole.
+    ///
+    /// A trailing backslash outside of quotes continues the line as well.
+    pub fn is_complete(&self, text: &str) -> bool {
+        let mut quote: Option<char> = None;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (Some(open), c) if c == open => quote = None,
+                // Only fish lets a backslash escape a quote inside single quotes.
+                (Some('\''), '\\') if *self == Shell::Fish => {
+                    chars.next();
+                }
+                (Some('\''), _) => {}
+                (Some(_), '\\') | (None, '\\') => {
+                    if chars.next().is_none() {
+                        return false;
+                    }
+                }
+                (Some(_), _) => {}
+                (None, '$') if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    quote = Some('$');
+                }
+                (None, '\'' | '"') => quote = Some(c),
+                (None, _) => {}
+            }
+
+            // `$'...'` strings end at a single quote, but allow escapes.
+            if quote == Some('$') {
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => {
+                            chars.next();
+                        }
+                        Some(_) => {}
+                        None => return false,
+                    }
+                }
+                quote = None;
+            }
+        }
+
+        quote.is_none()
+    }
+
+    /// The shell whose syntax an rc file is written in, judging by its name.
+    pub fn for_rc_file(path: &Path) -> Shell {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let extension = path.extension().unwrap_or_default();
+
+        if name.contains("zsh") || extension == "zsh" {
+            Shell::Zsh
+        } else if extension == "fish" {
+            Shell::Fish
+        } else if extension == "nu" {
+            Shell::Nu
+        } else {
+            Shell::Bash
+        }
+    }
+
+    /// The first character of `name` that this shell does not accept in an alias name.
+    ///
+    /// `bash` rejects `/`, `$`, backquotes, `=`, quoting characters and metacharacters.
+    /// `zsh` accepts `/` and `$`, since it only needs the name to be a single word.
+    /// `fish` defines aliases as functions, whose names cannot contain `/` or start with `-`.
+    /// In every shell, a leading `#` would start a comment, so the alias could never be typed.
+    pub fn illegal_name_char(&self, name: &str) -> Option<char> {
+        const METACHARACTERS: &str = "|&;()<>'\"\\`=";
+
+        if name.starts_with('#') {
+            return Some('#');
+        }
+
+        match self {
+            Shell::Bash | Shell::Nu => name
+                .chars()
+                .find(|&c| c.is_whitespace() || METACHARACTERS.contains(c) || "/$".contains(c)),
+            Shell::Zsh => name
+                .chars()
+                .find(|&c| c.is_whitespace() || METACHARACTERS.contains(c)),
+            Shell::Fish => {
+                if name.starts_with('-') {
+                    return Some('-');
+                }
+                name.chars()
+                    .find(|&c| c.is_whitespace() || "/=".contains(c))
+            }
+        }
+    }
+
+    /// Returns a hook script that, once evaluated by the shell, runs `binary` before every command.
+    ///
+    /// The hook pipes the shell's aliases into `binary` along with the typed command,
+    /// and ignores its exit status so the user's command always runs.
+    /// It only collects the `sources` that are enabled, since it does so before every command.
+    pub fn init_script(&self, binary: &str, sources: HookSources) -> String {
+        let binary = self.quote(binary);
+        let sources = self.hook_arguments(sources);
+
+        match self {
+            Shell::Zsh => format!(
+                r#"# alias-helper shell integration for zsh.
+# Load it from ~/.zshrc with: eval "$(alias-helper init zsh)"
+_alias_helper_preexec() {{
+  [[ -x {binary} ]] || return 0
+  alias | {binary} --shell zsh --quiet --session "${{TTY:-$$}}"{sources} -- "$1" || true
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec _alias_helper_preexec
+"#
+            ),
+            Shell::Bash => format!(
+                r#"# alias-helper shell integration for bash.
+# Load it from ~/.bashrc with: eval "$(alias-helper init bash)"
+{BASH_COMMAND_READER}
+_alias_helper_preexec() {{
+  _alias_helper_read_command || return 0
+  [[ -x {binary} ]] || return 0
+  alias | {binary} --shell bash --quiet --session "$$"{sources} -- "$_alias_helper_command" || true
+}}
+{BASH_TRAP_INSTALLER}
+{BASH_PROMPT_INSTALLER}
+"#
+            ),
+            Shell::Fish => format!(
+                r#"# alias-helper shell integration for fish.
+# Load it from ~/.config/fish/config.fish with: alias-helper init fish | source
+function __alias_helper_preexec --on-event fish_preexec
+    test -x {binary}; or return 0
+    alias | {binary} --shell fish --quiet --session $fish_pid{sources} -- $argv[1]; or true
+end
+"#
+            ),
+            Shell::Nu => format!(
+                r#"# alias-helper shell integration for nushell.
+# Save it with `alias-helper init nu | save -f ~/.cache/alias-helper.nu`
+# and load it from config.nu with `source ~/.cache/alias-helper.nu`.
+$env.config = ($env.config | upsert hooks.pre_execution (
+    ($env.config.hooks?.pre_execution? | default []) | append {{||
+        let command = (commandline)
+        do --ignore-errors {{
+            scope aliases
+            | each {{|alias| $"alias ($alias.name) = ($alias.expansion)" }}
+            | str join "\n"
+            | run-external {binary} --shell nu --quiet --session $nu.pid{sources} -- $command
+        }}
+    }}
+))
+"#
+            ),
+        }
+    }
+
+    /// Returns a hook script that also cancels the typed command when `binary` exits with `blocked_status`.
+    ///
+    /// Commands starting with a space or a backslash are never checked. Nushell can't cancel
+    /// a command from its hooks, so it has no strict script. Fish checks the command from its
+    /// Enter binding, which replaces any the user made in the default and insert modes.
+    pub fn strict_init_script(
+        &self,
+        binary: &str,
+        blocked_status: i32,
+        sources: HookSources,
+    ) -> Option<String> {
+        let binary = self.quote(binary);
+        let sources = self.hook_arguments(sources);
+
+        let script = match self {
+            Shell::Zsh => format!(
+                r#"# alias-helper strict shell integration for zsh.
+# Load it from ~/.zshrc with: eval "$(alias-helper init zsh --strict)"
+_alias_helper_accept_line() {{
+  if [[ -x {binary} && -n "$BUFFER" && "$BUFFER" != [[:space:]\\]* ]]; then
+    zle -I
+    alias | {binary} --shell zsh --quiet --strict --session "${{TTY:-$$}}"{sources} -- "$BUFFER"
+    (( $? == {blocked_status} )) && return 0
+  fi
+  zle .accept-line
+}}
+zle -N accept-line _alias_helper_accept_line
+"#
+            ),
+            Shell::Bash => format!(
+                r#"# alias-helper strict shell integration for bash.
+# Load it from ~/.bashrc with: eval "$(alias-helper init bash --strict)"
+{BASH_COMMAND_READER}
+_alias_helper_preexec() {{
+  _alias_helper_read_command || return 0
+  [[ -x {binary} && "$_alias_helper_command" != [[:space:]\\]* ]] || return 0
+  alias | {binary} --shell bash --quiet --strict --session "$$"{sources} -- "$_alias_helper_command"
+  (( $? != {blocked_status} ))
+}}
+shopt -s extdebug
+{BASH_TRAP_INSTALLER}
+{BASH_PROMPT_INSTALLER}
+"#
+            ),
+            Shell::Fish => format!(
+                r#"# alias-helper strict shell integration for fish.
+# Load it from ~/.config/fish/config.fish with: alias-helper init fish --strict | source
+# It takes over Enter in the default and insert modes, replacing your own binding there; a custom
+# Enter binding should call __alias_helper_execute instead of execute.
+function __alias_helper_execute
+    set -l command (commandline | string collect)
+    if test -x {binary}; and test -n "$command"; and not string match -qr '^[\s\\\\]' -- $command
+        alias | {binary} --shell fish --quiet --strict --session $fish_pid{sources} -- "$command"
+        if test $status -eq {blocked_status}
+            commandline -f repaint
+            return
+        end
+    end
+    commandline -f execute
+end
+for mode in default insert
+    bind -M $mode \r __alias_helper_execute
+    bind -M $mode \n __alias_helper_execute
+end
+"#
+            ),
+            Shell::Nu => return None,
+        };
+
+        Some(script)
+    }
+
+    /// The hook's arguments passing the enabled `sources`, each with a leading space. Sources this
+    /// shell can't list are left out.
+    fn hook_arguments(&self, sources: HookSources) -> String {
+        let (functions, named_dirs, env) = match self {
+            Shell::Zsh => (Some("<(typeset -f)"), Some("<(hash -d)"), Some("<(env)")),
+            Shell::Bash => (Some("<(declare -f)"), None, Some("<(env)")),
+            Shell::Fish => (Some("(functions -an | psub)"), None, None),
+            Shell::Nu => (None, None, None),
+        };
+
+        [
+            ("--functions", functions.filter(|_| sources.functions)),
+            ("--named-dirs", named_dirs.filter(|_| sources.named_dirs)),
+            ("--env", env.filter(|_| sources.env)),
+        ]
+        .into_iter()
+        .filter_map(|(flag, value)| Some(format!(" {} {}", flag, value?)))
+        .collect()
+    }
+
+    /// Quotes a word so this shell reads it back literally.
+    fn quote(&self, word: &str) -> String {
+        match self {
+            Shell::Zsh | Shell::Bash => format!("'{}'", word.replace('\'', r"'\''")),
+            Shell::Fish => format!("'{}'", word.replace('\\', r"\\").replace('\'', r"\'")),
+            // Raw strings end at a quote followed by as many `#` as they started with.
+            Shell::Nu => {
+                let hashes = "#".repeat(
+                    (1..)
+                        .find(|&count| !word.contains(&format!("'{}", "#".repeat(count))))
+                        .unwrap_or(1),
+                );
+                format!("r{0}'{1}'{0}", hashes, word)
+            }
+        }
+    }
+}
+
+/// Bash has no preexec hook, so the DEBUG trap is armed from PROMPT_COMMAND and reads the typed
+/// command from history. A history number that didn't change means the command wasn't recorded.
+const BASH_COMMAND_READER: &str = r#"_alias_helper_armed=
+_alias_helper_last_entry=
+_alias_helper_command=
+_alias_helper_arm() {
+  _alias_helper_armed=1
+}
+_alias_helper_read_command() {
+  [[ -n "$_alias_helper_armed" && -z "$COMP_LINE" ]] || return 1
+  _alias_helper_armed=
+  local entry
+  entry="$(HISTTIMEFORMAT= builtin history 1)"
+  [[ "$entry" =~ ^[[:space:]]*([0-9]+)[*[:space:]]+(.*)$ ]] || return 1
+  [[ "${BASH_REMATCH[1]}" != "$_alias_helper_last_entry" ]] || return 1
+  _alias_helper_last_entry="${BASH_REMATCH[1]}"
+  _alias_helper_command="${BASH_REMATCH[2]}"
+}"#;
+
+/// Bash has a single DEBUG trap, so an existing one, such as bash-preexec's, runs first and the
+/// hook is chained after it, whose status decides whether strict mode skips the command.
+/// Loading the hook twice doesn't chain it twice. Functions don't see the DEBUG trap, so this
+/// runs at the top level, with function tracing off so the trap doesn't run, and print, inside
+/// the command substitution that reads it.
+const BASH_TRAP_INSTALLER: &str = r#"_alias_helper_tracing=
+if shopt -q extdebug; then _alias_helper_tracing+=e; shopt -u extdebug; fi
+if [[ $- == *T* ]]; then _alias_helper_tracing+=T; set +T; fi
+eval "_alias_helper_previous_trap=($(trap -p DEBUG))"
+case "${_alias_helper_previous_trap[2]}" in
+  *_alias_helper_preexec*) ;;
+  '') trap '_alias_helper_preexec' DEBUG ;;
+  *) trap "${_alias_helper_previous_trap[2]}"$'\n''_alias_helper_preexec' DEBUG ;;
+esac
+if [[ $_alias_helper_tracing == *T* ]]; then set -T; fi
+if [[ $_alias_helper_tracing == *e* ]]; then shopt -s extdebug; fi
+unset _alias_helper_tracing _alias_helper_previous_trap"#;
+
+/// Arms the hook from PROMPT_COMMAND, once however many times the hook is loaded. Bash 5.1 runs
+/// every element of a PROMPT_COMMAND array, so the hook gets its own; older versions only run a
+/// string, which is extended on a new line so a trailing `;` in it stays valid.
+const BASH_PROMPT_INSTALLER: &str = r#"case "${PROMPT_COMMAND[*]}" in
+  *_alias_helper_arm*) ;;
+  *) if (( BASH_VERSINFO[0] > 5 || (BASH_VERSINFO[0] == 5 && BASH_VERSINFO[1] >= 1) )); then
+       PROMPT_COMMAND+=(_alias_helper_arm)
+     else
+       PROMPT_COMMAND="${PROMPT_COMMAND:+$PROMPT_COMMAND$'\n'}_alias_helper_arm"
+     fi ;;
+esac"#;
+
+/// Removes fish quoting from an alias value, where backslashes escape quotes even inside single quotes.
+fn unquote_fish(word: &str, line: &str) -> Result<String, AliasError> {
+    let parse_error = || AliasError::ParseError(line.to_string());
+    let mut output = String::new();
+    let mut chars = word.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            quote @ ('\'' | '"') => loop {
+                match chars.next() {
+                    Some(c) if c == quote => break,
+                    Some('\\') => match chars.next() {
+                        Some(c) if c == quote || c == '\\' || (quote == '"' && c == '$') => {
+                            output.push(c)
+                        }
+                        Some(c) => {
+                            output.push('\\');
+                            output.push(c);
+                        }
+                        None => return Err(parse_error()),
+                    },
+                    Some(c) => output.push(c),
+                    None => return Err(parse_error()),
+                }
+            },
+            '\\' => match chars.next() {
+                Some(c) => output.push(c),
+                None => return Err(parse_error()),
+            },
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "zsh" => Ok(Shell::Zsh),
+            "bash" => Ok(Shell::Bash),
+            "fish" => Ok(Shell::Fish),
+            "nu" | "nushell" => Ok(Shell::Nu),
+            _ => Err(format!(
+                "unsupported shell {:?}, expected one of: zsh, bash, fish, nu",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Shell::Zsh => "zsh",
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+            Shell::Nu => "nu",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HookSources, Shell};
+    use crate::Alias;
+
+    #[test]
+    fn it_parses_aliases_in_each_shell_format() {
+        let expected = Alias::from("gb='git branch'").unwrap();
+
+        assert_eq!(
+            Shell::Zsh.parse_alias("gb='git branch'"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Shell::Bash.parse_alias("alias gb='git branch'"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Shell::Fish.parse_alias("alias gb 'git branch'"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Shell::Nu.parse_alias("alias gb = git branch"),
+            Ok(expected.clone())
+        );
+    }
+
+    #[test]
+    fn it_unquotes_fish_escapes() {
+        assert_eq!(
+            Shell::Fish.parse_alias(r"alias say 'echo it\'s'"),
+            Ok(Alias::from(r"say='echo it'\''s'").unwrap())
+        );
+        Shell::Fish.parse_alias("alias broken 'echo").unwrap_err();
+        Shell::Fish.parse_alias("alias").unwrap_err();
+    }
+
+    #[test]
+    fn it_defines_aliases_that_parse_back() {
+        let alias = Alias::from(r"say='echo it'\''s'").unwrap();
+
+        for shell in [Shell::Zsh, Shell::Bash, Shell::Fish] {
+            let definition = shell.define_alias(&alias);
+            let printed = match shell {
+                Shell::Zsh => definition.trim_start_matches("alias ").to_string(),
+                _ => definition,
+            };
+            assert_eq!(shell.parse_alias(&printed), Ok(alias.clone()));
+        }
+        assert_eq!(
+            Shell::Nu.define_alias(&Alias::from("gb='git branch'").unwrap()),
+            "alias gb = git branch"
+        );
+    }
+
+    #[test]
+    fn it_detects_open_quotes() {
+        assert!(Shell::Zsh.is_complete("g='git'"));
+        assert!(Shell::Zsh.is_complete("q='it'\\''s'"));
+        assert!(!Shell::Zsh.is_complete("multi='echo one"));
+        assert!(!Shell::Bash.is_complete("alias x=\"echo \\\"a"));
+        assert!(!Shell::Zsh.is_complete("x=$'a\\'"));
+        assert!(!Shell::Zsh.is_complete("x=echo \\"));
+        assert!(Shell::Zsh.is_complete("x='a\\'"));
+        assert!(!Shell::Fish.is_complete("alias x 'a\\'"));
+    }
+
+    #[test]
+    fn it_parses_shell_names() {
+        assert_eq!("zsh".parse(), Ok(Shell::Zsh));
+        assert_eq!("nushell".parse(), Ok(Shell::Nu));
+        "powershell".parse::<Shell>().unwrap_err();
+    }
+
+    #[test]
+    fn it_generates_hooks_that_ignore_failures() {
+        for shell in [Shell::Zsh, Shell::Bash, Shell::Fish, Shell::Nu] {
+            let script = shell.init_script("/opt/alias helper", HookSources::default());
+            assert!(script.contains(&format!("--shell {} --quiet", shell)));
+            assert!(script.contains("alias helper"));
+        }
+
+        assert!(Shell::Zsh
+            .init_script("alias-helper", HookSources::default())
+            .contains("add-zsh-hook preexec"));
+        assert!(Shell::Bash
+            .init_script("alias-helper", HookSources::default())
+            .contains("trap '_alias_helper_preexec' DEBUG"));
+        assert!(Shell::Bash
+            .init_script("alias-helper", HookSources::default())
+            .contains("$(trap -p DEBUG)"));
+        assert!(Shell::Fish
+            .init_script("alias-helper", HookSources::default())
+            .contains("--on-event fish_preexec"));
+    }
+
+    #[test]
+    fn it_quotes_the_binary_for_each_shell() {
+        assert_eq!(Shell::Bash.quote("/opt/it's"), r"'/opt/it'\''s'");
+        assert_eq!(Shell::Fish.quote(r"/opt/it's\"), r"'/opt/it\'s\\'");
+        assert_eq!(Shell::Nu.quote("/opt/`it`"), "r#'/opt/`it`'#");
+        assert_eq!(Shell::Nu.quote("/opt/it'#s"), "r##'/opt/it'#s'##");
+    }
+
+    #[test]
+    fn it_only_collects_the_enabled_sources() {
+        let all = HookSources {
+            functions: true,
+            named_dirs: true,
+            env: true,
+        };
+        let functions = HookSources {
+            functions: true,
+            ..HookSources::default()
+        };
+
+        assert!(!Shell::Zsh
+            .init_script("alias-helper", HookSources::default())
+            .contains("typeset -f"));
+        assert!(Shell::Zsh
+            .init_script("alias-helper", all)
+            .contains("--functions <(typeset -f) --named-dirs <(hash -d) --env <(env) --"));
+        assert!(Shell::Bash
+            .init_script("alias-helper", all)
+            .contains("--functions <(declare -f) --env <(env) --"));
+        assert!(Shell::Bash
+            .strict_init_script("alias-helper", 42, functions)
+            .unwrap()
+            .contains("--functions <(declare -f) --"));
+        assert!(Shell::Fish
+            .init_script("alias-helper", all)
+            .contains("$fish_pid --functions (functions -an | psub) --"));
+        assert!(!Shell::Nu.init_script("alias-helper", all).contains("--env"));
+    }
+
+    #[test]
+    fn it_arms_the_bash_hook_after_an_existing_prompt_command() {
+        for script in [
+            Shell::Bash.init_script("alias-helper", HookSources::default()),
+            Shell::Bash
+                .strict_init_script("alias-helper", 42, HookSources::default())
+                .unwrap(),
+        ] {
+            let test = format!(
+                "PROMPT_COMMAND='_previous=1;'\n{0}\n{0}\n_alias_helper_arm() {{ _armed=1; }}\n\
+                 for command in \"${{PROMPT_COMMAND[@]}}\"; do eval \"$command\" || exit 1; done\n\
+                 echo \"$_previous $_armed ${{PROMPT_COMMAND[*]}}\"",
+                script
+            );
+            let Ok(output) = std::process::Command::new("bash")
+                .args(["--norc", "-c", &test])
+                .output()
+            else {
+                continue;
+            };
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{:?}", output);
+            assert!(stdout.starts_with("1 1 "), "{}", stdout);
+            assert_eq!(stdout.matches("_alias_helper_arm").count(), 1, "{}", stdout);
+        }
+    }
+
+    #[test]
+    fn it_generates_strict_hooks_that_cancel_blocked_commands() {
+        for shell in [Shell::Zsh, Shell::Bash, Shell::Fish] {
+            let script = shell
+                .strict_init_script("alias-helper", 42, HookSources::default())
+                .unwrap();
+            assert!(script.contains("--strict"));
+            assert!(script.contains("42"));
+        }
+
+        assert!(Shell::Zsh
+            .strict_init_script("alias-helper", 42, HookSources::default())
+            .unwrap()
+            .contains("zle -N accept-line"));
+        assert!(Shell::Bash
+            .strict_init_script("alias-helper", 42, HookSources::default())
+            .unwrap()
+            .contains("shopt -s extdebug"));
+        assert!(Shell::Fish
+            .strict_init_script("alias-helper", 42, HookSources::default())
+            .unwrap()
+            .contains("bind -M $mode \\r __alias_helper_execute"));
+        assert_eq!(
+            Shell::Nu.strict_init_script("alias-helper", 42, HookSources::default()),
+            None
+        );
+    }
+}
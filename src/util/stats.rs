@@ -0,0 +1,388 @@
+use super::log::*;
+use super::macros::*;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const DAY: u64 = 60 * 60 * 24;
+const WEEK: u64 = 7 * DAY;
+
+/// How far back the adaptive cooldown looks when deciding whether an alias is adopted.
+pub const ADOPTION_WINDOW: u64 = 4 * WEEK;
+
+/// How many times an alias has to be typed within the window to count as adopted.
+pub const ADOPTION_MIN_USES: usize = 5;
+
+/// What happened to an alias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    /// The alias was typed.
+    Used,
+    /// Its command was typed in full, and a reminder was printed.
+    Shown,
+    /// Its command was typed in full, but the cooldown or the log level held the reminder back.
+    Suppressed,
+}
+
+/// A single line of the event store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    /// Seconds since the epoch.
+    pub time: u64,
+    pub kind: EventKind,
+    pub alias: String,
+    /// The typed command, only kept when `record_commands` is on.
+    pub command: Option<String>,
+}
+
+/// The events recorded so far, read from an append-only file.
+#[derive(Debug, Clone, Default)]
+pub struct EventStore {
+    events: Vec<Event>,
+}
+
+/// How well an alias has been adopted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AliasStats {
+    pub alias: String,
+    pub used: usize,
+    /// How many times its command was typed in full, whether or not a reminder was shown.
+    pub missed: usize,
+    /// How many of those came after a reminder had already been shown, ignoring it.
+    pub ignored: usize,
+}
+
+/// Alias usage over one week, starting on a Monday.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeeklyUsage {
+    pub week: u64,
+    pub used: usize,
+    pub missed: usize,
+}
+
+impl EventStore {
+    /// `$XDG_DATA_HOME/alias-helper/events.tsv`, falling back to `~/.local/share`.
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+            .map(|data_home| data_home.join("alias-helper").join("events.tsv"))
+    }
+
+    /// Reads the store at `path`. A missing file is an empty store, and malformed lines are skipped.
+    pub fn open(path: &Path) -> EventStore {
+        let content = fs::read_to_string(path).unwrap_or_else(|err| {
+            debug!("[{}] cannot read {:?}: {}", function_name!(), path, err);
+            String::new()
+        });
+
+        let events = content
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.splitn(4, '\t').collect();
+                let [time, kind, alias, command] = fields[..] else {
+                    return None;
+                };
+
+                Some(Event {
+                    time: time.parse().ok()?,
+                    kind: kind.parse().ok()?,
+                    alias: alias.to_string(),
+                    command: Some(command.to_string()).filter(|command| !command.is_empty()),
+                })
+            })
+            .collect();
+
+        EventStore { events }
+    }
+
+    /// Adds `events` to the end of the store at `path`, creating it if needed.
+    pub fn append(path: &Path, events: &[Event]) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content: String = events
+            .iter()
+            .map(|event| {
+                format!(
+                    "{}\t{}\t{}\t{}\n",
+                    event.time,
+                    event.kind,
+                    single_line(&event.alias),
+                    event
+                        .command
+                        .as_deref()
+                        .map(single_line)
+                        .unwrap_or_default()
+                )
+            })
+            .collect();
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(content.as_bytes())
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The usage of every alias with events, the most used first.
+    pub fn aliases(&self) -> Vec<AliasStats> {
+        let mut stats: BTreeMap<&str, AliasStats> = BTreeMap::new();
+        let mut reminded: BTreeSet<&str> = BTreeSet::new();
+
+        for event in &self.events {
+            let entry = stats
+                .entry(&event.alias)
+                .or_insert_with(|| AliasStats::new(&event.alias));
+
+            match event.kind {
+                EventKind::Used => entry.used += 1,
+                EventKind::Shown | EventKind::Suppressed => {
+                    if reminded.contains(event.alias.as_str()) {
+                        entry.ignored += 1;
+                    }
+                    if event.kind == EventKind::Shown {
+                        reminded.insert(&event.alias);
+                    }
+                    entry.missed += 1;
+                }
+            }
+        }
+
+        let mut stats: Vec<AliasStats> = stats.into_values().collect();
+        stats.sort_by(|a, b| b.used.cmp(&a.used).then(a.alias.cmp(&b.alias)));
+        stats
+    }
+
+    /// Whether `alias` was typed instead of its command at least `percent` percent of the time over
+    /// the [ADOPTION_WINDOW] before `now`, and at least [ADOPTION_MIN_USES] times. An alias that
+    /// stops being used falls out of the window and is no longer adopted.
+    pub fn is_adopted(&self, alias: &str, percent: u8, now: u64) -> bool {
+        let since = now.saturating_sub(ADOPTION_WINDOW);
+        let mut stats = AliasStats::new(alias);
+
+        for event in self
+            .events
+            .iter()
+            .filter(|event| event.alias == alias && event.time >= since)
+        {
+            match event.kind {
+                EventKind::Used => stats.used += 1,
+                EventKind::Shown | EventKind::Suppressed => stats.missed += 1,
+            }
+        }
+
+        debug_value!(stats);
+        stats.used >= ADOPTION_MIN_USES && stats.adoption() * 100.0 >= f64::from(percent)
+    }
+
+    /// Usage across all aliases for each of the `weeks` weeks up to `now`, oldest first.
+    pub fn weekly(&self, weeks: u64, now: u64) -> Vec<WeeklyUsage> {
+        let current = week_start(now);
+        let mut usage: Vec<WeeklyUsage> = (0..weeks)
+            .rev()
+            .filter_map(|ago| current.checked_sub(ago * WEEK))
+            .map(|week| WeeklyUsage {
+                week,
+                used: 0,
+                missed: 0,
+            })
+            .collect();
+
+        for event in &self.events {
+            let week = week_start(event.time);
+            if let Some(entry) = usage.iter_mut().find(|entry| entry.week == week) {
+                match event.kind {
+                    EventKind::Used => entry.used += 1,
+                    EventKind::Shown | EventKind::Suppressed => entry.missed += 1,
+                }
+            }
+        }
+
+        usage
+    }
+}
+
+impl AliasStats {
+    fn new(alias: &str) -> AliasStats {
+        AliasStats {
+            alias: alias.to_string(),
+            used: 0,
+            missed: 0,
+            ignored: 0,
+        }
+    }
+
+    /// The share of times the alias was typed rather than its command, from 0 to 1.
+    pub fn adoption(&self) -> f64 {
+        adoption(self.used, self.missed)
+    }
+}
+
+impl WeeklyUsage {
+    pub fn adoption(&self) -> f64 {
+        adoption(self.used, self.missed)
+    }
+}
+
+fn adoption(used: usize, missed: usize) -> f64 {
+    match used + missed {
+        0 => 0.0,
+        total => used as f64 / total as f64,
+    }
+}
+
+/// The Monday the week of `time` starts on; the epoch was a Thursday.
+fn week_start(time: u64) -> u64 {
+    let offset = 3 * DAY;
+    ((time + offset) / WEEK * WEEK).saturating_sub(offset)
+}
+
+fn single_line(text: &str) -> String {
+    text.replace(['\t', '\n'], " ")
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "used" => Ok(EventKind::Used),
+            "shown" => Ok(EventKind::Shown),
+            "suppressed" => Ok(EventKind::Suppressed),
+            _ => Err(format!("unknown event {:?}", value)),
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::Used => write!(f, "used"),
+            EventKind::Shown => write!(f, "shown"),
+            EventKind::Suppressed => write!(f, "suppressed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, EventKind, EventStore, ADOPTION_WINDOW, DAY, WEEK};
+    use tempfile::TempDir;
+
+    const MONDAY: u64 = 1_699_833_600;
+
+    fn event(time: u64, kind: EventKind, alias: &str) -> Event {
+        Event {
+            time,
+            kind,
+            alias: alias.to_string(),
+            command: None,
+        }
+    }
+
+    #[test]
+    fn it_appends_and_reads_events() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data").join("events.tsv");
+
+        EventStore::append(&path, &[event(MONDAY, EventKind::Shown, "gst")]).unwrap();
+        let mut used = event(MONDAY + 1, EventKind::Used, "gst");
+        used.command = Some("gst\t-s".to_string());
+        EventStore::append(&path, &[used]).unwrap();
+
+        let store = EventStore::open(&path);
+        assert_eq!(store.events().len(), 2);
+        assert_eq!(store.events()[0].command, None);
+        assert_eq!(store.events()[1].command, Some("gst -s".to_string()));
+    }
+
+    #[test]
+    fn it_only_counts_misses_after_a_shown_reminder_as_ignored() {
+        let store = EventStore {
+            events: vec![
+                event(MONDAY, EventKind::Suppressed, "gco"),
+                event(MONDAY + 1, EventKind::Suppressed, "gco"),
+                event(MONDAY + 2, EventKind::Shown, "gco"),
+                event(MONDAY + 3, EventKind::Suppressed, "gco"),
+                event(MONDAY + 4, EventKind::Shown, "gst"),
+            ],
+        };
+
+        let stats: Vec<(String, usize, usize)> = store
+            .aliases()
+            .into_iter()
+            .map(|stats| (stats.alias, stats.missed, stats.ignored))
+            .collect();
+        assert_eq!(
+            stats,
+            vec![("gco".to_string(), 4, 1), ("gst".to_string(), 1, 0)]
+        );
+    }
+
+    #[test]
+    fn it_decides_when_an_alias_is_adopted() {
+        let mut events: Vec<Event> = (0..5)
+            .map(|day| event(MONDAY + day * DAY, EventKind::Used, "gco"))
+            .collect();
+        events.push(event(MONDAY + 5 * DAY, EventKind::Shown, "gco"));
+        let mut store = EventStore { events };
+        let now = MONDAY + 6 * DAY;
+
+        assert!(store.is_adopted("gco", 80, now));
+        assert!(!store.is_adopted("gco", 90, now));
+        assert!(!store.is_adopted("gst", 0, now));
+
+        // Typed in full twice more: 5 uses out of 8.
+        store.events.push(event(now, EventKind::Suppressed, "gco"));
+        store.events.push(event(now, EventKind::Suppressed, "gco"));
+        assert!(!store.is_adopted("gco", 80, now));
+
+        // Weeks later, the old uses have left the window.
+        assert!(!store.is_adopted("gco", 0, now + ADOPTION_WINDOW));
+    }
+
+    #[test]
+    fn it_summarizes_adoption() {
+        let store = EventStore {
+            events: vec![
+                event(MONDAY - WEEK, EventKind::Shown, "gco"),
+                event(MONDAY - WEEK + 1, EventKind::Suppressed, "gco"),
+                event(MONDAY - WEEK + 2, EventKind::Shown, "gco"),
+                event(MONDAY, EventKind::Used, "gco"),
+                event(MONDAY + DAY, EventKind::Used, "gco"),
+                event(MONDAY + DAY, EventKind::Used, "gst"),
+            ],
+        };
+
+        let gco = &store.aliases()[0];
+        assert_eq!((gco.used, gco.missed, gco.ignored), (2, 3, 2));
+        assert_eq!(gco.adoption(), 0.4);
+
+        let weeks = store.weekly(3, MONDAY + 2 * DAY);
+        let usage: Vec<(u64, usize, usize)> = weeks
+            .iter()
+            .map(|week| (week.week, week.used, week.missed))
+            .collect();
+        assert_eq!(
+            usage,
+            vec![
+                (MONDAY - 2 * WEEK, 0, 0),
+                (MONDAY - WEEK, 0, 3),
+                (MONDAY, 3, 0)
+            ]
+        );
+    }
+}
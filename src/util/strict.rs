@@ -0,0 +1,97 @@
+use super::alias::*;
+use super::config::*;
+use super::ignore::*;
+use super::log::*;
+use super::macros::*;
+
+/// Decides which matches block the typed command when alias-helper runs with `--strict`.
+#[derive(Debug, Clone, Default)]
+pub struct StrictMode {
+    exemptions: IgnoreRules,
+}
+
+impl StrictMode {
+    pub fn new(exemptions: IgnoreRules) -> StrictMode {
+        StrictMode { exemptions }
+    }
+
+    /// Builds the exemptions from the `strict_exempt_aliases` and `strict_exempt_commands` globs.
+    pub fn from_config(config: &Config) -> Result<StrictMode, ConfigError> {
+        let invalid =
+            |key: &str| ConfigError::InvalidValue(key.to_string(), config.source(key).to_owned());
+        let mut exemptions = vec![];
+
+        for glob in &config.strict_exempt_aliases {
+            exemptions.push(IgnoreRule::alias(glob).map_err(|_| invalid("strict_exempt_aliases"))?);
+        }
+
+        for glob in &config.strict_exempt_commands {
+            exemptions.push(
+                IgnoreRule::command_glob(glob).map_err(|_| invalid("strict_exempt_commands"))?,
+            );
+        }
+
+        Ok(StrictMode::new(IgnoreRules::new(exemptions)))
+    }
+
+    /// Whether `command` should be cancelled, given the aliases that match it.
+    pub fn blocks(&self, command: &str, matches: &[Alias]) -> bool {
+        debug_value!(command, matches);
+
+        if is_escaped(command) || self.exemptions.ignores_command(command) {
+            trace!("[{}] command is exempt", function_name!());
+            return false;
+        }
+
+        matches
+            .iter()
+            .any(|alias| !self.exemptions.ignores_alias(alias))
+    }
+}
+
+/// Whether the command starts with a space or a backslash, which asks alias-helper to leave it alone.
+pub fn is_escaped(command: &str) -> bool {
+    command.starts_with([' ', '\t', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_escaped, StrictMode};
+    use crate::{Alias, IgnoreRule, IgnoreRules};
+
+    #[test]
+    fn it_blocks_commands_with_matches() {
+        let strict = StrictMode::default();
+        let matches = vec![Alias::from("gb='git branch'").unwrap()];
+
+        assert!(strict.blocks("git branch", &matches));
+        assert!(!strict.blocks("git branch", &[]));
+    }
+
+    #[test]
+    fn it_lets_escaped_commands_through() {
+        let strict = StrictMode::default();
+        let matches = vec![Alias::from("gb='git branch'").unwrap()];
+
+        assert!(is_escaped(" git branch"));
+        assert!(is_escaped("\\git branch"));
+        assert!(!is_escaped("git branch"));
+        assert!(!strict.blocks(" git branch", &matches));
+        assert!(!strict.blocks("\\git branch", &matches));
+    }
+
+    #[test]
+    fn it_respects_exemptions() {
+        let strict = StrictMode::new(IgnoreRules::new(vec![
+            IgnoreRule::alias("g").unwrap(),
+            IgnoreRule::command_glob("git push --force").unwrap(),
+        ]));
+
+        assert!(!strict.blocks("git", &[Alias::from("g='git'").unwrap()]));
+        assert!(!strict.blocks(
+            "git push --force origin",
+            &[Alias::from("gp='git push'").unwrap()]
+        ));
+        assert!(strict.blocks("git push origin", &[Alias::from("gp='git push'").unwrap()]));
+    }
+}
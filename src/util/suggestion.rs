@@ -0,0 +1,40 @@
+use serde::Serialize;
+use std::fmt;
+
+/// The kind of shortcut a suggestion is based on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuggestionKind {
+    /// A shell function that passes its arguments through to a single command.
+    Function,
+    /// A zsh named directory, typed as `~name`.
+    NamedDirectory,
+    /// An exported variable, typed as `$NAME`.
+    EnvVariable,
+}
+
+/// A shorter way to type a command, spelled out in full.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    /// The name of the shortcut, such as the function name.
+    pub shortcut: String,
+    /// What to type instead of the command.
+    pub rewritten: String,
+}
+
+impl fmt::Display for SuggestionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SuggestionKind::Function => write!(f, "function"),
+            SuggestionKind::NamedDirectory => write!(f, "named directory"),
+            SuggestionKind::EnvVariable => write!(f, "environment variable"),
+        }
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rewritten)
+    }
+}
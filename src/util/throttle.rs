@@ -0,0 +1,340 @@
+use super::log::*;
+use super::macros::*;
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const DAY: u64 = 60 * 60 * 24;
+
+/// Entries not updated for this long are dropped when the store is saved.
+const RETENTION: u64 = 30 * DAY;
+
+/// When a reminder for the same command may be shown again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CooldownPolicy {
+    /// Every time the command is typed.
+    #[default]
+    Always,
+    /// Once per shell session.
+    OncePerSession,
+    /// At most the given number of times a day, across sessions.
+    Daily(u32),
+    /// After the given number of seconds, doubled after every reminder, across sessions.
+    Backoff(u64),
+    /// Until the alias is typed instead of its command at least this percentage of the time,
+    /// and again once it no longer is. Relies on the events kept while `record_stats` is on.
+    Adaptive(u8),
+}
+
+/// How many times a reminder was shown for a command in a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReminderEntry {
+    session: String,
+    command: String,
+    count: u32,
+    last_shown: u64,
+    day_count: u32,
+}
+
+/// A small on-disk store of the reminders shown so far, keyed by session and command.
+#[derive(Debug, Clone, Default)]
+pub struct ReminderStore {
+    entries: Vec<ReminderEntry>,
+}
+
+impl ReminderStore {
+    /// `$XDG_STATE_HOME/alias-helper/reminders.tsv`, falling back to `~/.local/state`.
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+            .map(|state_home| state_home.join("alias-helper").join("reminders.tsv"))
+    }
+
+    /// Reads the store at `path`. A missing file is an empty store, and malformed lines are skipped.
+    pub fn open(path: &Path) -> ReminderStore {
+        let content = fs::read_to_string(path).unwrap_or_else(|err| {
+            debug!("[{}] cannot read {:?}: {}", function_name!(), path, err);
+            String::new()
+        });
+
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.splitn(5, '\t').collect();
+                let [session, count, last_shown, day_count, command] = fields[..] else {
+                    return None;
+                };
+
+                Some(ReminderEntry {
+                    session: session.to_string(),
+                    command: command.to_string(),
+                    count: count.parse().ok()?,
+                    last_shown: last_shown.parse().ok()?,
+                    day_count: day_count.parse().ok()?,
+                })
+            })
+            .collect();
+
+        ReminderStore { entries }
+    }
+
+    /// Writes the store to `path`, dropping entries older than a month. The content goes to a
+    /// temporary file first, renamed over `path`, so shells saving at the same time never leave
+    /// a half-written store behind.
+    pub fn save(&self, path: &Path, now: u64) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content: String = self
+            .entries
+            .iter()
+            .filter(|entry| entry.last_shown + RETENTION > now)
+            .map(|entry| {
+                format!(
+                    "{}\t{}\t{}\t{}\t{}\n",
+                    entry.session, entry.count, entry.last_shown, entry.day_count, entry.command
+                )
+            })
+            .collect();
+
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(format!(".{}.tmp", std::process::id()));
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, path).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+    }
+
+    /// Whether `policy` allows a reminder for `command` in `session` at `now`, in seconds since the epoch.
+    pub fn should_remind(
+        &self,
+        policy: CooldownPolicy,
+        session: &str,
+        command: &str,
+        now: u64,
+    ) -> bool {
+        let (session, command) = (normalize(session), normalize(command));
+        let mut entries = self.entries.iter().filter(|entry| entry.command == command);
+
+        match policy {
+            CooldownPolicy::Always => true,
+            CooldownPolicy::OncePerSession => !entries.any(|entry| entry.session == session),
+            CooldownPolicy::Daily(limit) => {
+                let today: u32 = entries
+                    .filter(|entry| entry.last_shown / DAY == now / DAY)
+                    .map(|entry| entry.day_count)
+                    .sum();
+                today < limit
+            }
+            CooldownPolicy::Backoff(base) => {
+                let (count, last_shown) = entries.fold((0, 0), |(count, last), entry| {
+                    (count + entry.count, last.max(entry.last_shown))
+                });
+
+                if count == 0 {
+                    return true;
+                }
+
+                let wait = base.saturating_mul(1u64.checked_shl(count - 1).unwrap_or(u64::MAX));
+                now >= last_shown.saturating_add(wait)
+            }
+            // Decided per alias from the usage statistics rather than from past reminders.
+            CooldownPolicy::Adaptive(_) => true,
+        }
+    }
+
+    /// Records that a reminder for `command` was shown in `session` at `now`.
+    pub fn record(&mut self, session: &str, command: &str, now: u64) {
+        let (session, command) = (normalize(session), normalize(command));
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.session == session && entry.command == command)
+        {
+            Some(entry) => {
+                entry.day_count = if entry.last_shown / DAY == now / DAY {
+                    entry.day_count + 1
+                } else {
+                    1
+                };
+                entry.count += 1;
+                entry.last_shown = now;
+            }
+            None => self.entries.push(ReminderEntry {
+                session,
+                command,
+                count: 1,
+                last_shown: now,
+                day_count: 1,
+            }),
+        }
+    }
+}
+
+/// Collapses whitespace so a session or command fits in a single field of the store.
+fn normalize(command: &str) -> String {
+    command.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Identifies the current shell session by its terminal, or by the parent process when there is none.
+pub fn current_session() -> String {
+    fs::read_link("/proc/self/fd/2")
+        .ok()
+        .map(|path| path.display().to_string())
+        .filter(|path| path.starts_with("/dev/"))
+        .unwrap_or_else(|| std::os::unix::process::parent_id().to_string())
+}
+
+/// Seconds since the epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+impl FromStr for CooldownPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "unsupported cooldown {:?}, expected always, once-per-session, daily:<count>, backoff:<seconds> or adaptive:<percent>",
+                value
+            )
+        };
+
+        match value.split_once(':') {
+            None if value == "always" => Ok(CooldownPolicy::Always),
+            None if value == "once-per-session" => Ok(CooldownPolicy::OncePerSession),
+            None if value == "backoff" => Ok(CooldownPolicy::Backoff(60)),
+            None if value == "adaptive" => Ok(CooldownPolicy::Adaptive(80)),
+            Some(("daily", limit)) => limit
+                .parse()
+                .map(CooldownPolicy::Daily)
+                .map_err(|_| invalid()),
+            Some(("backoff", seconds)) => seconds
+                .parse()
+                .map(CooldownPolicy::Backoff)
+                .map_err(|_| invalid()),
+            Some(("adaptive", percent)) => percent
+                .trim_end_matches('%')
+                .parse()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .map(CooldownPolicy::Adaptive)
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for CooldownPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CooldownPolicy::Always => write!(f, "always"),
+            CooldownPolicy::OncePerSession => write!(f, "once-per-session"),
+            CooldownPolicy::Daily(limit) => write!(f, "daily:{}", limit),
+            CooldownPolicy::Backoff(seconds) => write!(f, "backoff:{}", seconds),
+            CooldownPolicy::Adaptive(percent) => write!(f, "adaptive:{}", percent),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CooldownPolicy, ReminderStore, DAY};
+    use tempfile::TempDir;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn it_parses_policies() {
+        assert_eq!("always".parse(), Ok(CooldownPolicy::Always));
+        assert_eq!(
+            "once-per-session".parse(),
+            Ok(CooldownPolicy::OncePerSession)
+        );
+        assert_eq!("daily:3".parse(), Ok(CooldownPolicy::Daily(3)));
+        assert_eq!("backoff".parse(), Ok(CooldownPolicy::Backoff(60)));
+        assert_eq!("backoff:10".parse(), Ok(CooldownPolicy::Backoff(10)));
+        assert_eq!("adaptive".parse(), Ok(CooldownPolicy::Adaptive(80)));
+        assert_eq!("adaptive:90%".parse(), Ok(CooldownPolicy::Adaptive(90)));
+        "adaptive:120".parse::<CooldownPolicy>().unwrap_err();
+        "daily".parse::<CooldownPolicy>().unwrap_err();
+        "hourly:2".parse::<CooldownPolicy>().unwrap_err();
+    }
+
+    #[test]
+    fn it_reminds_once_per_session() {
+        let mut store = ReminderStore::default();
+        let policy = CooldownPolicy::OncePerSession;
+
+        assert!(store.should_remind(policy, "tty1", "git status", NOW));
+        store.record("tty1", "git status", NOW);
+        assert!(!store.should_remind(policy, "tty1", "git  status", NOW + 1));
+        assert!(store.should_remind(policy, "tty2", "git status", NOW + 1));
+        assert!(store.should_remind(policy, "tty1", "git branch", NOW + 1));
+    }
+
+    #[test]
+    fn it_limits_reminders_per_day() {
+        let mut store = ReminderStore::default();
+        let policy = CooldownPolicy::Daily(2);
+
+        store.record("tty1", "git status", NOW);
+        assert!(store.should_remind(policy, "tty1", "git status", NOW));
+        store.record("tty2", "git status", NOW);
+        assert!(!store.should_remind(policy, "tty1", "git status", NOW));
+        assert!(store.should_remind(policy, "tty1", "git status", NOW + DAY));
+    }
+
+    #[test]
+    fn it_backs_off_exponentially() {
+        let mut store = ReminderStore::default();
+        let policy = CooldownPolicy::Backoff(10);
+
+        store.record("tty1", "git status", NOW);
+        assert!(!store.should_remind(policy, "tty1", "git status", NOW + 9));
+        assert!(store.should_remind(policy, "tty1", "git status", NOW + 10));
+
+        store.record("tty1", "git status", NOW + 10);
+        assert!(!store.should_remind(policy, "tty1", "git status", NOW + 29));
+        assert!(store.should_remind(policy, "tty1", "git status", NOW + 30));
+    }
+
+    #[test]
+    fn it_saves_and_reopens_the_store() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state").join("reminders.tsv");
+        let mut store = ReminderStore::default();
+        store.record("/dev/pts/1", "git status", NOW);
+        store.record("/dev/pts/1", "git log", NOW - 31 * DAY);
+        store.save(&path, NOW).unwrap();
+
+        let store = ReminderStore::open(&path);
+        let policy = CooldownPolicy::OncePerSession;
+        assert!(!store.should_remind(policy, "/dev/pts/1", "git status", NOW));
+        assert!(store.should_remind(policy, "/dev/pts/1", "git log", NOW));
+
+        // Sessions with a tab are stored and looked up the same way.
+        let mut store = ReminderStore::open(&path);
+        store.record("odd\tsession", "git status", NOW);
+        store.save(&path, NOW).unwrap();
+        let store = ReminderStore::open(&path);
+        assert!(!store.should_remind(policy, "odd\tsession", "git status", NOW));
+        assert!(std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .all(|entry| !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".tmp")));
+    }
+}
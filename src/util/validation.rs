@@ -7,7 +7,7 @@ use super::super::Alias;
 use log::*;
 use std::collections::HashSet;
 
-pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
+pub fn remove_cycles(aliases: &[Alias]) -> Vec<Alias> {
     debug_value!(aliases);
     let mut visited = HashSet::new();
     let mut stack = Vec::new(); // Use Vec to maintain order of traversal
@@ -15,7 +15,7 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
 
     fn dfs(
         node: &str,
-        aliases: &Vec<Alias>,
+        aliases: &[Alias],
         visited: &mut HashSet<String>,
         stack: &mut Vec<String>,
         in_cycle: &mut HashSet<String>,
@@ -24,8 +24,8 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
         if stack.contains(&node.to_string()) {
             trace!("[{}] stack contains node", function_name!());
             let cycle_start_index = stack.iter().position(|n| n == node).unwrap();
-            for i in cycle_start_index..stack.len() {
-                in_cycle.insert(stack[i].clone());
+            for name in stack.iter().skip(cycle_start_index) {
+                in_cycle.insert(name.clone());
             }
             return;
         }
@@ -36,7 +36,7 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
         debug!("[{}] pushing {:?} to stack", function_name!(), node);
         stack.push(node.to_string());
 
-        let Some(node) = &aliases.into_iter().find(|alias| alias.name.get() == node) else {
+        let Some(node) = &aliases.iter().find(|alias| alias.name.get() == node) else {
             debug!(
                 "[{}] Cannot find {:?} under commands: {:?}",
                 function_name!(),
@@ -47,14 +47,14 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
             return;
         };
 
-        if node.command.get().trim().len() == 0 {
+        if node.command.get().trim().is_empty() {
             trace!("[{}] node has no command", function_name!());
             debug_value!(node);
             stack.pop();
             return;
         }
 
-        let command = &node.command.get().split_whitespace().nth(0).unwrap();
+        let command = &node.command.get().split_whitespace().next().unwrap();
 
         trace!("[{}] calling dfs again", function_name!());
         debug_value!(command, aliases, visited, stack, in_cycle);
@@ -66,8 +66,8 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
 
     for alias in aliases {
         dfs(
-            &alias.name.get(),
-            &aliases,
+            alias.name.get(),
+            aliases,
             &mut visited,
             &mut stack,
             &mut in_cycle,
@@ -76,7 +76,7 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
 
     // Remove nodes found in cycles from the original map
     let mut result: Vec<Alias> = vec![];
-    for alias in aliases.into_iter() {
+    for alias in aliases.iter() {
         if !in_cycle.contains(alias.name.get()) {
             result.push(alias.clone());
         }
@@ -87,10 +87,52 @@ pub fn remove_cycles(aliases: &Vec<Alias>) -> Vec<Alias> {
     result
 }
 
-pub fn filter_invalid_aliases(aliases: &Vec<Alias>) -> Vec<Alias> {
+/// Every cycle of aliases whose commands start with one another, as the path that leads back to
+/// its first alias, e.g. `["c", "d", "c"]`. Each cycle is listed once, starting with the alias
+/// defined first.
+pub fn find_cycles(aliases: &[Alias]) -> Vec<Vec<String>> {
+    let next = |name: &str| -> Option<String> {
+        let alias = aliases.iter().find(|alias| alias.name.get() == name)?;
+        alias
+            .command
+            .get()
+            .split_whitespace()
+            .next()
+            .map(str::to_string)
+    };
+
+    let mut cycles: Vec<Vec<String>> = vec![];
+    for alias in aliases {
+        let mut path = vec![alias.name.get().to_owned()];
+
+        while let Some(node) = next(path.last().unwrap()) {
+            if let Some(start) = path.iter().position(|name| *name == node) {
+                // Only report the cycle from the alias it was first reached from.
+                if start == 0 {
+                    let mut cycle = path.to_owned();
+                    cycle.push(node);
+                    let members: HashSet<&String> = cycle.iter().collect();
+                    if !cycles
+                        .iter()
+                        .any(|other| other.iter().collect::<HashSet<_>>() == members)
+                    {
+                        cycles.push(cycle);
+                    }
+                }
+                break;
+            }
+            path.push(node);
+        }
+    }
+
+    debug_value!(cycles);
+    cycles
+}
+
+pub fn filter_invalid_aliases(aliases: &[Alias]) -> Vec<Alias> {
     let aliases: Vec<Alias> = aliases
         .iter()
-        .filter(|alias| alias.command.get().trim().len() > 0)
+        .filter(|alias| !alias.command.get().trim().is_empty())
         .map(Clone::clone)
         .collect();
 
@@ -124,6 +166,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_finds_cycles_with_their_path() {
+        let aliases: Vec<Alias> = vec![
+            Alias::from("b='b'").unwrap(),
+            Alias::from("c='d'").unwrap(),
+            Alias::from("d='c'").unwrap(),
+            Alias::from("e='f -i'").unwrap(),
+            Alias::from("f='g -l'").unwrap(),
+            Alias::from("g='f -y'").unwrap(),
+            Alias::from("h='git'").unwrap(),
+        ];
+
+        assert_eq!(
+            validation::find_cycles(&aliases),
+            vec![vec!["b", "b"], vec!["c", "d", "c"], vec!["f", "g", "f"],]
+        );
+    }
+
     #[test]
     fn it_doesnt_filter_valid_aliases() {
         let aliases: Vec<Alias> = vec![
@@ -0,0 +1,14 @@
+CREATE TABLE history (
+    id TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    duration INTEGER NOT NULL,
+    exit INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    cwd TEXT NOT NULL,
+    session TEXT NOT NULL,
+    hostname TEXT NOT NULL,
+    deleted_at INTEGER
+);
+INSERT INTO history VALUES ('b', 1700000060000000000, 1000, 0, 'cargo test', '/home/me/work/crate', 's1', 'host:me', NULL);
+INSERT INTO history VALUES ('a', 1700000000000000000, 1000, 0, 'git status', '/home/me', 's1', 'host:me', NULL);
+INSERT INTO history VALUES ('c', 1700000030000000000, 1000, 0, 'rm secrets', '/home/me', 's1', 'host:me', 1700000040000000000);